@@ -0,0 +1,239 @@
+pub mod console {
+    use crate::frame::frame::parse_hex;
+    use crate::source::source::Source;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    /// bytes going to and coming from a port, shared between its reader thread and any console
+    /// panes so the port does not have to be closed to talk to the device
+    #[derive(Debug, Default)]
+    pub struct Console {
+        outgoing: Mutex<Vec<Vec<u8>>>,
+        subscribers: Mutex<Vec<Arc<Mutex<Vec<u8>>>>>,
+    }
+    /// what one console pane has not seen yet of the bytes read from the port
+    #[derive(Debug)]
+    pub struct Incoming(Arc<Mutex<Vec<u8>>>);
+    impl Incoming {
+        pub fn take(&self) -> Vec<u8> {
+            std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+        }
+    }
+    impl Console {
+        const INCOMING_LIMIT: usize = 1 << 16;
+        pub fn send(&self, bytes: Vec<u8>) {
+            self.outgoing
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(bytes);
+        }
+        /// everything read from now on, nothing is kept for a port without subscribers
+        pub fn subscribe(&self) -> Incoming {
+            let incoming = Arc::new(Mutex::new(vec![]));
+            self.subscribers
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(incoming.clone());
+            Incoming(incoming)
+        }
+        fn received(&self, bytes: &[u8]) {
+            let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+            // the pane holding the other reference was closed
+            subscribers.retain(|incoming| Arc::strong_count(incoming) > 1);
+            for incoming in subscribers.iter() {
+                let mut incoming = incoming.lock().unwrap_or_else(|e| e.into_inner());
+                incoming.extend_from_slice(bytes);
+                if incoming.len() > Console::INCOMING_LIMIT {
+                    let extra = incoming.len() - Console::INCOMING_LIMIT;
+                    incoming.drain(..extra);
+                }
+            }
+        }
+        fn take_outgoing(&self) -> Vec<Vec<u8>> {
+            std::mem::take(&mut *self.outgoing.lock().unwrap_or_else(|e| e.into_inner()))
+        }
+    }
+    /// wraps the source of a reader thread, copying everything read into the console and writing
+    /// out whatever the console queued before every read
+    #[derive(Debug)]
+    pub struct ConsoleSource {
+        source: Box<dyn Source>,
+        console: Arc<Console>,
+    }
+    impl ConsoleSource {
+        pub fn new(source: Box<dyn Source>, console: Arc<Console>) -> Self {
+            ConsoleSource { source, console }
+        }
+        fn write_outgoing(&mut self) -> std::io::Result<()> {
+            for bytes in self.console.take_outgoing() {
+                self.source.write_all(&bytes)?;
+            }
+            Ok(())
+        }
+    }
+    impl std::io::Read for ConsoleSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.write_outgoing()?;
+            let n = self.source.read(buf)?;
+            self.console.received(&buf[..n]);
+            Ok(n)
+        }
+    }
+    impl std::io::Write for ConsoleSource {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.source.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.source.flush()
+        }
+    }
+    impl Source for ConsoleSource {
+        fn datagrams(&self) -> bool {
+            self.source.datagrams()
+        }
+        fn recorded_at(&self) -> Option<Duration> {
+            self.source.recorded_at()
+        }
+        fn writable(&self) -> bool {
+            self.source.writable()
+        }
+    }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum LineEnding {
+        None,
+        Lf,
+        Cr,
+        CrLf,
+    }
+    impl LineEnding {
+        pub const ALL: [LineEnding; 4] = [
+            LineEnding::None,
+            LineEnding::Lf,
+            LineEnding::Cr,
+            LineEnding::CrLf,
+        ];
+        fn bytes(&self) -> &'static [u8] {
+            match self {
+                LineEnding::None => b"",
+                LineEnding::Lf => b"\n",
+                LineEnding::Cr => b"\r",
+                LineEnding::CrLf => b"\r\n",
+            }
+        }
+    }
+    impl std::fmt::Display for LineEnding {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    LineEnding::None => "no line ending",
+                    LineEnding::Lf => "LF",
+                    LineEnding::Cr => "CR",
+                    LineEnding::CrLf => "CR LF",
+                }
+            )
+        }
+    }
+    pub struct ConsolePane {
+        pub name: String,
+        console: Arc<Console>,
+        incoming: Incoming,
+        pub input: String,
+        pub line_ending: LineEnding,
+        pub hex: bool,
+        /// (sent by us, text) oldest first
+        pub scrollback: Vec<(bool, String)>,
+        partial_line: String,
+    }
+    impl ConsolePane {
+        const SCROLLBACK: usize = 1000;
+        pub fn new(name: String, console: Arc<Console>) -> Self {
+            ConsolePane {
+                name,
+                incoming: console.subscribe(),
+                console,
+                input: String::new(),
+                line_ending: LineEnding::Lf,
+                hex: false,
+                scrollback: vec![],
+                partial_line: String::new(),
+            }
+        }
+        fn push(&mut self, sent: bool, line: String) {
+            self.scrollback.push((sent, line));
+            if self.scrollback.len() > ConsolePane::SCROLLBACK {
+                self.scrollback.remove(0);
+            }
+        }
+        /// queues the input for the port, or says why it could not
+        pub fn send(&mut self) -> Result<(), String> {
+            let mut bytes = if self.hex {
+                parse_hex(&self.input).ok_or(format!("{:?} is not hex", self.input))?
+            } else {
+                self.input.as_bytes().to_vec()
+            };
+            bytes.extend_from_slice(self.line_ending.bytes());
+            let line = std::mem::take(&mut self.input);
+            self.push(true, line);
+            self.console.send(bytes);
+            Ok(())
+        }
+        pub fn update(&mut self) {
+            let incoming = self.incoming.take();
+            if incoming.is_empty() {
+                return;
+            }
+            if self.hex {
+                let line = incoming
+                    .iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                self.push(false, line);
+                return;
+            }
+            for c in String::from_utf8_lossy(&incoming).chars() {
+                match c {
+                    '\n' => {
+                        let line = std::mem::take(&mut self.partial_line);
+                        self.push(false, line);
+                    }
+                    '\r' => {}
+                    c => self.partial_line.push(c),
+                }
+            }
+        }
+        /// received text that has not been ended by a newline yet
+        pub fn partial_line(&self) -> &str {
+            &self.partial_line
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        #[test]
+        fn every_pane_sees_everything() {
+            let console = Arc::new(Console::default());
+            let mut first = ConsolePane::new("a".to_string(), console.clone());
+            let mut second = ConsolePane::new("b".to_string(), console.clone());
+            console.received(b"hello\nwor");
+            first.update();
+            console.received(b"ld\n");
+            first.update();
+            second.update();
+            for pane in [&first, &second] {
+                assert_eq!(
+                    pane.scrollback,
+                    vec![(false, "hello".to_string()), (false, "world".to_string())]
+                );
+            }
+            drop(second);
+            console.received(b"!");
+            assert_eq!(console.subscribers.lock().unwrap().len(), 1);
+            // a late pane starts from what arrives after it opened
+            let late = console.subscribe();
+            console.received(b"?");
+            assert_eq!(late.take(), b"?");
+        }
+    }
+}
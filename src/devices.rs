@@ -0,0 +1,218 @@
+pub mod devices {
+    use serialport::{SerialPortType, UsbPortInfo};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc;
+    use std::time::Duration;
+    /// an entry of the port list, `path` is what gets opened
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Device {
+        pub path: String,
+        /// the kernel's name for a device opened by its /dev/serial/by-id alias
+        pub kernel_path: Option<String>,
+        pub usb: Option<UsbPortInfo>,
+        /// given by the user, see `Names`
+        pub name: Option<String>,
+    }
+    impl Device {
+        /// something without USB information, like "dummy" or an address
+        pub fn new(path: &str) -> Self {
+            Device {
+                path: path.to_string(),
+                kernel_path: None,
+                usb: None,
+                name: None,
+            }
+        }
+        pub fn serial_number(&self) -> Option<&str> {
+            self.usb.as_ref()?.serial_number.as_deref()
+        }
+        pub fn named(mut self, names: &Names) -> Self {
+            self.name = self
+                .serial_number()
+                .and_then(|serial_number| names.get(serial_number))
+                .map(String::from);
+            self
+        }
+    }
+    impl std::fmt::Display for Device {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            if let Some(name) = &self.name {
+                write!(f, "{}: ", name)?;
+            }
+            // by-id aliases are long, the kernel name is enough to tell them apart
+            write!(f, "{}", self.kernel_path.as_ref().unwrap_or(&self.path))?;
+            if let Some(usb) = &self.usb {
+                write!(f, " {:04x}:{:04x}", usb.vid, usb.pid)?;
+                for text in [&usb.manufacturer, &usb.product].into_iter().flatten() {
+                    write!(f, " {}", text)?;
+                }
+                if let Some(serial_number) = &usb.serial_number {
+                    write!(f, " #{}", serial_number)?;
+                }
+            }
+            Ok(())
+        }
+    }
+    const BY_ID: &str = "/dev/serial/by-id";
+    /// kernel device paths and the stable /dev/serial/by-id links to them, empty off Linux
+    pub fn by_id_aliases() -> HashMap<String, String> {
+        aliases_in(Path::new(BY_ID))
+    }
+    fn aliases_in(dir: &Path) -> HashMap<String, String> {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let target = std::fs::canonicalize(entry.path()).ok()?;
+                Some((
+                    target.to_string_lossy().to_string(),
+                    entry.path().to_string_lossy().to_string(),
+                ))
+            })
+            .collect()
+    }
+    /// the kernel's name for a device opened by an alias, which is what `available_ports` lists
+    pub fn kernel_path(path: &str) -> String {
+        std::fs::canonicalize(path)
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or(path.to_string())
+    }
+    /// serial ports with their USB information, opened by their by-id alias when they have one
+    pub fn serial_devices() -> serialport::Result<Vec<Device>> {
+        let aliases = by_id_aliases();
+        Ok(serialport::available_ports()?
+            .into_iter()
+            .map(|port| {
+                let usb = match port.port_type {
+                    SerialPortType::UsbPort(usb) => Some(usb),
+                    _ => None,
+                };
+                match aliases.get(&port.port_name) {
+                    Some(alias) => Device {
+                        path: alias.clone(),
+                        kernel_path: Some(port.port_name),
+                        usb,
+                        name: None,
+                    },
+                    None => Device {
+                        path: port.port_name,
+                        kernel_path: None,
+                        usb,
+                        name: None,
+                    },
+                }
+            })
+            .collect())
+    }
+    /// lists the serial ports every second on a thread of its own, since enumerating them can
+    /// take a while, and sends the list (or why there is none) whenever it changes
+    pub fn watch_serial_devices() -> mpsc::Receiver<Result<Vec<Device>, String>> {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut last = None;
+            loop {
+                let devices = serial_devices().map_err(|e| e.to_string());
+                if last.as_ref() != Some(&devices) {
+                    if sender.send(devices.clone()).is_err() {
+                        return;
+                    }
+                    last = Some(devices);
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        });
+        receiver
+    }
+    /// friendly names of devices by USB serial number, kept in the user's config directory
+    /// (`~/.config/project/port_names` on Linux) as one `serial number<TAB>name` line each
+    #[derive(Debug, Default)]
+    pub struct Names {
+        names: Vec<(String, String)>,
+    }
+    impl Names {
+        /// where names were kept before, relative to wherever the app was started
+        const OLD_PATH: &'static str = ".port_names";
+        fn path() -> Option<PathBuf> {
+            let home = || std::env::var_os("HOME").map(PathBuf::from);
+            let config = if cfg!(windows) {
+                std::env::var_os("APPDATA").map(PathBuf::from)
+            } else if cfg!(target_os = "macos") {
+                home().map(|home| home.join("Library/Application Support"))
+            } else {
+                std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .filter(|path| path.is_absolute())
+                    .or_else(|| home().map(|home| home.join(".config")))
+            };
+            Some(config?.join(env!("CARGO_PKG_NAME")).join("port_names"))
+        }
+        pub fn load() -> Self {
+            let text = Names::path()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .or_else(|| std::fs::read_to_string(Names::OLD_PATH).ok())
+                .unwrap_or_default();
+            Names {
+                names: text
+                    .lines()
+                    .filter_map(|line| line.split_once('\t'))
+                    .map(|(serial_number, name)| (serial_number.to_string(), name.to_string()))
+                    .collect(),
+            }
+        }
+        pub fn get(&self, serial_number: &str) -> Option<&str> {
+            self.names
+                .iter()
+                .find(|(s, _)| s == serial_number)
+                .map(|(_, name)| name.as_str())
+        }
+        /// an empty name forgets the device
+        pub fn set(&mut self, serial_number: &str, name: &str) -> std::io::Result<()> {
+            self.names.retain(|(s, _)| s != serial_number);
+            if !name.is_empty() {
+                self.names
+                    .push((serial_number.to_string(), name.replace(['\t', '\n'], " ")));
+            }
+            let path = Names::path().ok_or(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no config directory for this user",
+            ))?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(
+                path,
+                self.names
+                    .iter()
+                    .map(|(serial_number, name)| format!("{}\t{}\n", serial_number, name))
+                    .collect::<String>(),
+            )
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        #[cfg(unix)]
+        #[test]
+        fn aliases_map_to_kernel_names() {
+            let dir = std::env::temp_dir().join(format!("by-id-{}", std::process::id()));
+            let by_id = dir.join("by-id");
+            std::fs::create_dir_all(&by_id).unwrap();
+            let device = dir.join("ttyUSB7");
+            std::fs::write(&device, b"").unwrap();
+            let alias = by_id.join("usb-FTDI_FT232R_A10K3XYZ-if00-port0");
+            std::os::unix::fs::symlink("../ttyUSB7", &alias).unwrap();
+            let kernel = std::fs::canonicalize(&device)
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            let alias = alias.to_string_lossy().to_string();
+            assert_eq!(kernel_path(&alias), kernel);
+            assert_eq!(kernel_path(&kernel), kernel);
+            assert_eq!(kernel_path("COM3"), "COM3");
+            assert_eq!(aliases_in(&by_id).get(&kernel), Some(&alias));
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,433 @@
+pub mod firmata {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    const DIGITAL_MESSAGE: u8 = 0x90;
+    const ANALOG_MESSAGE: u8 = 0xE0;
+    const REPORT_ANALOG: u8 = 0xC0;
+    const REPORT_DIGITAL: u8 = 0xD0;
+    const SET_PIN_MODE: u8 = 0xF4;
+    const SET_DIGITAL_PIN_VALUE: u8 = 0xF5;
+    const REPORT_VERSION: u8 = 0xF9;
+    const START_SYSEX: u8 = 0xF0;
+    const END_SYSEX: u8 = 0xF7;
+    const EXTENDED_ANALOG: u8 = 0x6F;
+    const CAPABILITY_QUERY: u8 = 0x6B;
+    const CAPABILITY_RESPONSE: u8 = 0x6C;
+    const REPORT_FIRMWARE: u8 = 0x79;
+    const INPUT: u8 = 0;
+    const OUTPUT: u8 = 1;
+    const PWM: u8 = 3;
+    /// a pin reported by the board, `A0` is analog input 0, `D2` digital pin 2
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Pin {
+        Analog(u8),
+        Digital(u8),
+    }
+    impl std::fmt::Display for Pin {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            match self {
+                Pin::Analog(pin) => write!(f, "A{}", pin),
+                Pin::Digital(pin) => write!(f, "D{}", pin),
+            }
+        }
+    }
+    /// parses pins typed like "A0 A1 D2 7", a bare number is a digital pin
+    pub fn parse_pins(s: &str) -> Result<Vec<Pin>, String> {
+        s.split([',', ' '])
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                let pin = match token.chars().next() {
+                    Some('A' | 'a') => token[1..].parse().map(Pin::Analog),
+                    Some('D' | 'd') => token[1..].parse().map(Pin::Digital),
+                    _ => token.parse().map(Pin::Digital),
+                };
+                match pin {
+                    Ok(pin @ (Pin::Analog(0..16) | Pin::Digital(0..128))) => Ok(pin),
+                    _ => Err(format!("bad pin {:?}", token)),
+                }
+            })
+            .collect()
+    }
+    /// messages sent to set up reporting for `pins`, sent again whenever the board (re)starts
+    pub fn report(pins: &[Pin]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut ports = vec![];
+        for pin in pins {
+            match *pin {
+                Pin::Analog(pin) => bytes.extend([REPORT_ANALOG | pin, 1]),
+                Pin::Digital(pin) => {
+                    bytes.extend([SET_PIN_MODE, pin, INPUT]);
+                    if !ports.contains(&(pin / 8)) {
+                        ports.push(pin / 8);
+                    }
+                }
+            }
+        }
+        for port in ports {
+            bytes.extend([REPORT_DIGITAL | port, 1]);
+        }
+        bytes
+    }
+    /// asks for the protocol version, firmware name and what each pin can do, which is the
+    /// handshake
+    pub fn query() -> Vec<u8> {
+        vec![
+            REPORT_VERSION,
+            START_SYSEX,
+            REPORT_FIRMWARE,
+            END_SYSEX,
+            START_SYSEX,
+            CAPABILITY_QUERY,
+            END_SYSEX,
+        ]
+    }
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Message {
+        /// analog pin and its 14 bit value
+        Analog(u8, u16),
+        /// port (8 pins) and the state of its pins
+        Digital(u8, u8),
+        Version(u8, u8),
+        Firmware(String),
+        /// the modes every pin supports, by pin number
+        Capabilities(Vec<Vec<u8>>),
+        /// anything this client does not use
+        Other,
+    }
+    /// splits what the board sends into messages, skipping bytes that do not belong to one
+    #[derive(Debug, Default)]
+    pub struct Decoder {
+        buffer: Vec<u8>,
+        pub malformed: usize,
+    }
+    impl Decoder {
+        pub fn push(&mut self, bytes: &[u8]) {
+            self.buffer.extend_from_slice(bytes);
+        }
+        pub fn next_message(&mut self) -> Option<Message> {
+            loop {
+                match self.buffer.iter().position(|b| b & 0x80 != 0) {
+                    Some(0) => {}
+                    Some(start) => {
+                        self.malformed += 1;
+                        self.buffer.drain(..start);
+                    }
+                    None => {
+                        self.malformed += !self.buffer.is_empty() as usize;
+                        self.buffer.clear();
+                        return None;
+                    }
+                }
+                let command = self.buffer[0];
+                let len = match command & 0xF0 {
+                    DIGITAL_MESSAGE | ANALOG_MESSAGE => 3,
+                    REPORT_ANALOG | REPORT_DIGITAL => 2,
+                    _ if command == START_SYSEX => {
+                        match self.buffer[1..].iter().position(|b| b & 0x80 != 0) {
+                            Some(end) if self.buffer[end + 1] == END_SYSEX => end + 2,
+                            Some(_) => 1,
+                            None => return None,
+                        }
+                    }
+                    _ if command == REPORT_VERSION => 3,
+                    _ => 1,
+                };
+                if self.buffer.len() < len {
+                    return None;
+                }
+                let data = &self.buffer[1..len];
+                // another command where data should be means this one was cut short
+                if (command == START_SYSEX && len == 1)
+                    || (command != START_SYSEX && data.iter().any(|b| b & 0x80 != 0))
+                {
+                    self.malformed += 1;
+                    self.buffer.drain(..1);
+                    continue;
+                }
+                let message = match command & 0xF0 {
+                    ANALOG_MESSAGE => {
+                        Message::Analog(command & 0x0F, data[0] as u16 | (data[1] as u16) << 7)
+                    }
+                    DIGITAL_MESSAGE => Message::Digital(command & 0x0F, data[0] | data[1] << 7),
+                    _ if command == REPORT_VERSION => Message::Version(data[0], data[1]),
+                    // F0 79 major minor name (7 bit pairs) F7
+                    _ if command == START_SYSEX
+                        && data.len() >= 4
+                        && data[0] == REPORT_FIRMWARE =>
+                    {
+                        Message::Firmware(
+                            data[3..data.len() - 1]
+                                .chunks(2)
+                                .map(|c| (c[0] | c.get(1).map_or(0, |b| b << 7)) as char)
+                                .collect(),
+                        )
+                    }
+                    // F0 6C (mode resolution)* 7F for every pin F7
+                    _ if command == START_SYSEX && data[0] == CAPABILITY_RESPONSE => {
+                        Message::Capabilities(match data[1..data.len() - 1].split_last() {
+                            Some((0x7F, pins)) => pins
+                                .split(|b| *b == 0x7F)
+                                .map(|modes| modes.chunks(2).map(|c| c[0]).collect())
+                                .collect(),
+                            _ => vec![],
+                        })
+                    }
+                    _ => Message::Other,
+                };
+                self.buffer.drain(..len);
+                return Some(message);
+            }
+        }
+    }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Command {
+        /// picks the pin the output controls act on
+        Select(u8),
+        Digital(u8, bool),
+        /// duty cycle, 0 to 255 on most boards
+        Pwm(u8, u16),
+    }
+    #[derive(Debug, Clone, Default)]
+    pub struct State {
+        pub version: Option<(u8, u8)>,
+        pub firmware: String,
+        /// pins that can be outputs and whether they can do PWM, empty until the board said
+        pub output_pins: Vec<(u8, bool)>,
+        pub selected: u8,
+        /// last thing written to each output pin
+        pub outputs: HashMap<u8, Command>,
+        outgoing: Vec<u8>,
+    }
+    /// a Firmata board shared between its reader thread and the graphs of its pins
+    #[derive(Debug, Default)]
+    pub struct Board {
+        state: Mutex<State>,
+    }
+    impl Board {
+        fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+            self.state.lock().unwrap_or_else(|e| e.into_inner())
+        }
+        pub fn state(&self) -> State {
+            self.lock().clone()
+        }
+        pub fn apply(&self, command: Command) {
+            let mut state = self.lock();
+            match command {
+                Command::Select(pin) => state.selected = pin,
+                Command::Digital(pin, high) => {
+                    state.outgoing.extend([
+                        SET_PIN_MODE,
+                        pin,
+                        OUTPUT,
+                        SET_DIGITAL_PIN_VALUE,
+                        pin,
+                        high as u8,
+                    ]);
+                    state.outputs.insert(pin, command);
+                }
+                Command::Pwm(pin, value) => {
+                    let (lsb, msb) = ((value & 0x7F) as u8, (value >> 7 & 0x7F) as u8);
+                    state.outgoing.extend([SET_PIN_MODE, pin, PWM]);
+                    if pin < 16 {
+                        state.outgoing.extend([ANALOG_MESSAGE | pin, lsb, msb]);
+                    } else {
+                        state.outgoing.extend([
+                            START_SYSEX,
+                            EXTENDED_ANALOG,
+                            pin,
+                            lsb,
+                            msb,
+                            END_SYSEX,
+                        ]);
+                    }
+                    state.outputs.insert(pin, command);
+                }
+            }
+        }
+        /// bytes the reader thread still has to write to the board
+        pub fn take_outgoing(&self) -> Vec<u8> {
+            std::mem::take(&mut self.lock().outgoing)
+        }
+        pub fn received(&self, message: &Message) {
+            let mut state = self.lock();
+            match message {
+                Message::Version(major, minor) => state.version = Some((*major, *minor)),
+                Message::Firmware(name) => state.firmware = name.clone(),
+                Message::Capabilities(pins) => {
+                    state.output_pins = (0..)
+                        .zip(pins)
+                        .filter(|(_, modes)| modes.contains(&OUTPUT) || modes.contains(&PWM))
+                        .map(|(pin, modes)| (pin, modes.contains(&PWM)))
+                        .collect();
+                    if !state
+                        .output_pins
+                        .iter()
+                        .any(|(pin, _)| *pin == state.selected)
+                    {
+                        state.selected = state.output_pins.first().map_or(0, |(pin, _)| *pin);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        /// what an Uno running StandardFirmata 2.5 sends after `query()` and `report()`, cut
+        /// down to four pins, with bootloader noise in front and a message cut short
+        fn recording() -> Vec<u8> {
+            let mut bytes = vec![0x00, 0x3F, 0x12];
+            bytes.extend([REPORT_VERSION, 2, 5]);
+            bytes.extend([START_SYSEX, REPORT_FIRMWARE, 2, 5]);
+            for c in "StandardFirmata.ino".bytes() {
+                bytes.extend([c & 0x7F, c >> 7]);
+            }
+            bytes.push(END_SYSEX);
+            bytes.extend([START_SYSEX, CAPABILITY_RESPONSE]);
+            // D0 input/output, D1 nothing (serial), D2 input/output/PWM, A0 input/analog
+            bytes.extend([INPUT, 1, OUTPUT, 1, 0x7F]);
+            bytes.extend([0x7F]);
+            bytes.extend([INPUT, 1, OUTPUT, 1, PWM, 8, 0x7F]);
+            bytes.extend([INPUT, 1, 2, 10, 0x7F]);
+            bytes.push(END_SYSEX);
+            // A0 = 421, D2 high
+            bytes.extend([ANALOG_MESSAGE, 0x25, 0x03]);
+            bytes.extend([DIGITAL_MESSAGE, 0x04, 0x00]);
+            // A1 lost its second data byte
+            bytes.extend([ANALOG_MESSAGE | 1, 0x05]);
+            bytes.extend([DIGITAL_MESSAGE, 0x00, 0x00]);
+            // a string message, which is not used
+            bytes.extend([START_SYSEX, 0x71, b'h', 0, b'i', 0, END_SYSEX]);
+            // A15 at the 14 bit maximum
+            bytes.extend([ANALOG_MESSAGE | 15, 0x7F, 0x7F]);
+            bytes
+        }
+        fn expected() -> Vec<Message> {
+            vec![
+                Message::Version(2, 5),
+                Message::Firmware("StandardFirmata.ino".to_string()),
+                Message::Capabilities(vec![
+                    vec![INPUT, OUTPUT],
+                    vec![],
+                    vec![INPUT, OUTPUT, PWM],
+                    vec![INPUT, 2],
+                ]),
+                Message::Analog(0, 421),
+                Message::Digital(0, 0x04),
+                Message::Digital(0, 0x00),
+                Message::Other,
+                Message::Analog(15, 0x3FFF),
+            ]
+        }
+        fn messages(decoder: &mut Decoder) -> Vec<Message> {
+            std::iter::from_fn(|| decoder.next_message()).collect()
+        }
+        #[test]
+        fn recorded_board() {
+            let mut decoder = Decoder::default();
+            decoder.push(&recording());
+            assert_eq!(messages(&mut decoder), expected());
+            // the noise, the cut short message and its orphaned data byte
+            assert_eq!(decoder.malformed, 3);
+            assert!(decoder.buffer.is_empty());
+        }
+        #[test]
+        fn recorded_board_byte_by_byte() {
+            let mut decoder = Decoder::default();
+            let mut received = vec![];
+            for byte in recording() {
+                decoder.push(&[byte]);
+                received.extend(messages(&mut decoder));
+            }
+            assert_eq!(received, expected());
+            assert!(decoder.malformed >= 3);
+        }
+        #[test]
+        fn unfinished_sysex_waits() {
+            let mut decoder = Decoder::default();
+            decoder.push(&[START_SYSEX, REPORT_FIRMWARE, 2, 5, b'S', 0]);
+            assert_eq!(decoder.next_message(), None);
+            decoder.push(&[END_SYSEX]);
+            assert_eq!(
+                decoder.next_message(),
+                Some(Message::Firmware("S".to_string()))
+            );
+            assert_eq!(decoder.malformed, 0);
+        }
+        #[test]
+        fn pins() {
+            assert_eq!(
+                parse_pins("A0 a1,D2 7"),
+                Ok(vec![
+                    Pin::Analog(0),
+                    Pin::Analog(1),
+                    Pin::Digital(2),
+                    Pin::Digital(7)
+                ])
+            );
+            assert!(parse_pins("A16").is_err());
+            assert!(parse_pins("D128").is_err());
+            assert!(parse_pins("X1").is_err());
+            assert_eq!(
+                report(&[Pin::Analog(1), Pin::Digital(2), Pin::Digital(9)]),
+                vec![
+                    REPORT_ANALOG | 1,
+                    1,
+                    SET_PIN_MODE,
+                    2,
+                    INPUT,
+                    SET_PIN_MODE,
+                    9,
+                    INPUT,
+                    REPORT_DIGITAL,
+                    1,
+                    REPORT_DIGITAL | 1,
+                    1
+                ]
+            );
+        }
+        #[test]
+        fn outputs() {
+            let board = Board::default();
+            let mut decoder = Decoder::default();
+            decoder.push(&recording());
+            for message in messages(&mut decoder) {
+                board.received(&message);
+            }
+            let state = board.state();
+            assert_eq!(state.version, Some((2, 5)));
+            assert_eq!(state.output_pins, vec![(0, false), (2, true)]);
+            board.apply(Command::Digital(13, true));
+            board.apply(Command::Pwm(9, 1000));
+            board.apply(Command::Pwm(20, 5));
+            assert_eq!(
+                board.take_outgoing(),
+                vec![
+                    SET_PIN_MODE,
+                    13,
+                    OUTPUT,
+                    SET_DIGITAL_PIN_VALUE,
+                    13,
+                    1,
+                    SET_PIN_MODE,
+                    9,
+                    PWM,
+                    ANALOG_MESSAGE | 9,
+                    0x68,
+                    0x07,
+                    SET_PIN_MODE,
+                    20,
+                    PWM,
+                    START_SYSEX,
+                    EXTENDED_ANALOG,
+                    20,
+                    5,
+                    0,
+                    END_SYSEX
+                ]
+            );
+            assert!(board.take_outgoing().is_empty());
+        }
+    }
+}
@@ -0,0 +1,576 @@
+pub mod frame {
+    /// checksum appended after the payload, computed over the length byte (if any) and the payload
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Checksum {
+        None,
+        Xor,
+        /// CRC-8/SMBUS (poly 0x07, init 0x00)
+        Crc8,
+        /// CRC-16/CCITT-FALSE (poly 0x1021, init 0xFFFF), sent big endian
+        Crc16,
+    }
+    impl Checksum {
+        pub const ALL: [Checksum; 4] = [
+            Checksum::None,
+            Checksum::Xor,
+            Checksum::Crc8,
+            Checksum::Crc16,
+        ];
+        fn len(&self) -> usize {
+            match self {
+                Checksum::None => 0,
+                Checksum::Xor | Checksum::Crc8 => 1,
+                Checksum::Crc16 => 2,
+            }
+        }
+        fn compute(&self, data: &[u8]) -> Vec<u8> {
+            match self {
+                Checksum::None => vec![],
+                Checksum::Xor => vec![data.iter().fold(0, |acc, b| acc ^ b)],
+                Checksum::Crc8 => vec![data.iter().fold(0_u8, |mut crc, b| {
+                    crc ^= b;
+                    for _ in 0..8 {
+                        crc = if crc & 0x80 != 0 {
+                            (crc << 1) ^ 0x07
+                        } else {
+                            crc << 1
+                        };
+                    }
+                    crc
+                })],
+                Checksum::Crc16 => data
+                    .iter()
+                    .fold(0xFFFF_u16, |mut crc, b| {
+                        crc ^= (*b as u16) << 8;
+                        for _ in 0..8 {
+                            crc = if crc & 0x8000 != 0 {
+                                (crc << 1) ^ 0x1021
+                            } else {
+                                crc << 1
+                            };
+                        }
+                        crc
+                    })
+                    .to_be_bytes()
+                    .to_vec(),
+            }
+        }
+    }
+    impl std::fmt::Display for Checksum {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Checksum::None => "no checksum",
+                    Checksum::Xor => "XOR",
+                    Checksum::Crc8 => "CRC-8",
+                    Checksum::Crc16 => "CRC-16",
+                }
+            )
+        }
+    }
+    /// layout of a frame: `marker [length] payload [checksum]`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct FrameFormat {
+        pub marker: Vec<u8>,
+        pub length_byte: bool,
+        pub checksum: Checksum,
+    }
+    impl Default for FrameFormat {
+        fn default() -> Self {
+            FrameFormat {
+                marker: vec![0xAA, 0x55],
+                length_byte: false,
+                checksum: Checksum::Xor,
+            }
+        }
+    }
+    impl FrameFormat {
+        /// marker, length byte if there is one, payload and checksum
+        pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+            let mut frame = self.marker.clone();
+            let checked = frame.len();
+            if self.length_byte {
+                frame.push(payload.len() as u8);
+            }
+            frame.extend_from_slice(payload);
+            let checksum = self.checksum.compute(&frame[checked..]);
+            frame.extend(checksum);
+            frame
+        }
+    }
+    /// how frames are delimited on the wire
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Framing {
+        /// payloads back to back with nothing in between
+        Raw,
+        Marker(FrameFormat),
+        /// Consistent Overhead Byte Stuffing, every packet ends with a zero byte
+        Cobs,
+        /// RFC 1055, every packet ends with 0xC0
+        Slip,
+    }
+    impl Framing {
+        /// `payload` as it goes over the wire with this framing
+        pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+            match self {
+                Framing::Raw => payload.to_vec(),
+                Framing::Marker(format) => format.encode(payload),
+                Framing::Cobs => cobs_encode(payload),
+                Framing::Slip => slip_encode(payload),
+            }
+        }
+    }
+    /// `Framing` without its settings, for picking one
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum FramingKind {
+        Raw,
+        Marker,
+        Cobs,
+        Slip,
+    }
+    impl FramingKind {
+        pub const ALL: [FramingKind; 4] = [
+            FramingKind::Raw,
+            FramingKind::Marker,
+            FramingKind::Cobs,
+            FramingKind::Slip,
+        ];
+    }
+    impl std::fmt::Display for FramingKind {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    FramingKind::Raw => "unframed",
+                    FramingKind::Marker => "marker framed",
+                    FramingKind::Cobs => "COBS packets",
+                    FramingKind::Slip => "SLIP packets",
+                }
+            )
+        }
+    }
+    fn cobs_encode(payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0];
+        let mut code = 0;
+        for &b in payload {
+            if b != 0 {
+                packet.push(b);
+            }
+            if b == 0 || packet.len() - code == 0xFF {
+                packet[code] = (packet.len() - code) as u8;
+                code = packet.len();
+                packet.push(0);
+            }
+        }
+        packet[code] = (packet.len() - code) as u8;
+        packet.push(0);
+        packet
+    }
+    /// a packet without its trailing zero, None if a code byte points past the end
+    fn cobs_decode(packet: &[u8]) -> Option<Vec<u8>> {
+        let mut payload = Vec::with_capacity(packet.len());
+        let mut rest = packet;
+        while let Some((&code, tail)) = rest.split_first() {
+            let len = (code as usize).checked_sub(1)?;
+            if tail.len() < len {
+                return None;
+            }
+            payload.extend_from_slice(&tail[..len]);
+            rest = &tail[len..];
+            if code < 0xFF && !rest.is_empty() {
+                payload.push(0);
+            }
+        }
+        Some(payload)
+    }
+    const SLIP_END: u8 = 0xC0;
+    const SLIP_ESC: u8 = 0xDB;
+    const SLIP_ESC_END: u8 = 0xDC;
+    const SLIP_ESC_ESC: u8 = 0xDD;
+    /// starts with an END too, so line noise before the packet ends up in an empty packet
+    fn slip_encode(payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![SLIP_END];
+        for &b in payload {
+            match b {
+                SLIP_END => packet.extend([SLIP_ESC, SLIP_ESC_END]),
+                SLIP_ESC => packet.extend([SLIP_ESC, SLIP_ESC_ESC]),
+                b => packet.push(b),
+            }
+        }
+        packet.push(SLIP_END);
+        packet
+    }
+    /// a packet without its END, None on an escape that is not followed by ESC_END or ESC_ESC
+    fn slip_decode(packet: &[u8]) -> Option<Vec<u8>> {
+        let mut payload = Vec::with_capacity(packet.len());
+        let mut bytes = packet.iter();
+        while let Some(&b) = bytes.next() {
+            payload.push(match b {
+                SLIP_ESC => match bytes.next() {
+                    Some(&SLIP_ESC_END) => SLIP_END,
+                    Some(&SLIP_ESC_ESC) => SLIP_ESC,
+                    _ => return None,
+                },
+                b => b,
+            });
+        }
+        Some(payload)
+    }
+    /// parses bytes typed as hex, e.g. "AA 55" or "0xAA55"
+    pub fn parse_hex(s: &str) -> Option<Vec<u8>> {
+        let digits: String = s
+            .split_whitespace()
+            .map(|part| part.trim_start_matches("0x").trim_start_matches("0X"))
+            .collect();
+        if digits.is_empty()
+            || !digits.len().is_multiple_of(2)
+            || !digits.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return None;
+        }
+        digits
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    }
+    /// buffers raw bytes and hands out checked payloads, dropping bytes until the next marker
+    /// whenever a frame does not check out, or skipping to the next delimiter for packets
+    #[derive(Debug)]
+    pub struct Framer {
+        framing: Framing,
+        /// the marker format, or one without a marker for everything else
+        format: FrameFormat,
+        buffer: Vec<u8>,
+        /// the first packet is usually cut off since the port was opened halfway through it
+        synced: bool,
+        pub rejected: usize,
+        /// packets that did not decode or decoded to the wrong length
+        pub malformed: usize,
+    }
+    impl Framer {
+        pub fn new(framing: Framing) -> Self {
+            let format = match &framing {
+                Framing::Marker(format) => format.clone(),
+                _ => FrameFormat {
+                    marker: vec![],
+                    length_byte: false,
+                    checksum: Checksum::None,
+                },
+            };
+            Framer {
+                framing,
+                format,
+                buffer: vec![],
+                synced: false,
+                rejected: 0,
+                malformed: 0,
+            }
+        }
+        pub fn push(&mut self, bytes: &[u8]) {
+            self.buffer.extend_from_slice(bytes);
+        }
+        pub fn next_frame(&mut self, payload_len: usize) -> Option<Vec<u8>> {
+            match self.framing {
+                Framing::Cobs | Framing::Slip => self.next_packet(payload_len),
+                Framing::Raw | Framing::Marker(_) => self.next_marked(payload_len),
+            }
+        }
+        fn next_packet(&mut self, payload_len: usize) -> Option<Vec<u8>> {
+            let slip = self.framing == Framing::Slip;
+            let delimiter = if slip { SLIP_END } else { 0 };
+            loop {
+                let Some(end) = self.buffer.iter().position(|b| *b == delimiter) else {
+                    // no encoding more than doubles a packet, so this is not one
+                    if self.buffer.len() > 2 * payload_len + 2 {
+                        self.buffer.clear();
+                        self.malformed += 1;
+                        self.synced = false;
+                    }
+                    return None;
+                };
+                let packet: Vec<u8> = self.buffer.drain(..=end).take(end).collect();
+                if !std::mem::replace(&mut self.synced, true) || packet.is_empty() {
+                    continue;
+                }
+                let payload = if slip {
+                    slip_decode(&packet)
+                } else {
+                    cobs_decode(&packet)
+                };
+                match payload {
+                    Some(payload) if payload.len() == payload_len => return Some(payload),
+                    _ => self.malformed += 1,
+                }
+            }
+        }
+        fn next_marked(&mut self, payload_len: usize) -> Option<Vec<u8>> {
+            let marker_len = self.format.marker.len();
+            let header_len = marker_len + self.format.length_byte as usize;
+            let frame_len = header_len + payload_len + self.format.checksum.len();
+            loop {
+                match self
+                    .buffer
+                    .windows(marker_len.max(1))
+                    .position(|w| marker_len == 0 || w == self.format.marker.as_slice())
+                {
+                    Some(start) => {
+                        self.buffer.drain(..start);
+                    }
+                    None => {
+                        // keep what could still be the start of a marker
+                        let keep = marker_len.saturating_sub(1).min(self.buffer.len());
+                        self.buffer.drain(..self.buffer.len() - keep);
+                        return None;
+                    }
+                }
+                if self.buffer.len() < frame_len {
+                    return None;
+                }
+                let checked = &self.buffer[marker_len..header_len + payload_len];
+                let length_ok =
+                    !self.format.length_byte || self.buffer[marker_len] as usize == payload_len;
+                if length_ok
+                    && self.format.checksum.compute(checked)
+                        == self.buffer[header_len + payload_len..frame_len]
+                {
+                    let payload = self.buffer[header_len..header_len + payload_len].to_vec();
+                    self.buffer.drain(..frame_len);
+                    return Some(payload);
+                }
+                self.rejected += 1;
+                self.buffer.drain(..1);
+            }
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        /// payloads with zeros in every position and runs around the 254 byte COBS block
+        fn payloads() -> Vec<Vec<u8>> {
+            let mut payloads = vec![vec![0], vec![0, 0, 0], vec![1], vec![0, 1, 0, 2, 0]];
+            for len in [253, 254, 255, 256, 508, 600] {
+                payloads.push((0..len).map(|i| (i % 255 + 1) as u8).collect());
+                payloads.push((0..len).map(|i| (i % 7) as u8).collect());
+            }
+            payloads.push(vec![
+                SLIP_END,
+                SLIP_ESC,
+                1,
+                SLIP_ESC_END,
+                SLIP_ESC,
+                SLIP_END,
+            ]);
+            payloads.push((0..=255).collect());
+            payloads
+        }
+        #[test]
+        fn cobs_round_trip() {
+            for payload in payloads() {
+                let packet = cobs_encode(&payload);
+                let (last, body) = packet.split_last().unwrap();
+                assert_eq!(*last, 0);
+                assert!(!body.contains(&0), "zero inside {:?}", payload);
+                assert!(body.len() <= payload.len() + payload.len() / 254 + 1);
+                assert_eq!(cobs_decode(body), Some(payload));
+            }
+        }
+        #[test]
+        fn cobs_rejects_bad_codes() {
+            assert_eq!(cobs_decode(&[5, 1, 2]), None);
+            assert_eq!(cobs_decode(&[0, 1]), None);
+        }
+        #[test]
+        fn slip_round_trip() {
+            for payload in payloads() {
+                let packet = slip_encode(&payload);
+                assert_eq!(packet[0], SLIP_END);
+                let (last, body) = packet[1..].split_last().unwrap();
+                assert_eq!(*last, SLIP_END);
+                assert!(!body.contains(&SLIP_END));
+                assert_eq!(slip_decode(body), Some(payload));
+            }
+        }
+        #[test]
+        fn slip_rejects_bad_escapes() {
+            assert_eq!(slip_decode(&[1, SLIP_ESC, 2]), None);
+            assert_eq!(slip_decode(&[1, SLIP_ESC]), None);
+        }
+        fn delimiter(framing: &Framing) -> u8 {
+            match framing {
+                Framing::Slip => SLIP_END,
+                _ => 0,
+            }
+        }
+        fn frames(framer: &mut Framer, len: usize) -> Vec<Vec<u8>> {
+            std::iter::from_fn(|| framer.next_frame(len)).collect()
+        }
+        #[test]
+        fn packets_resync_after_garbage() {
+            for framing in [Framing::Cobs, Framing::Slip] {
+                let good: Vec<Vec<u8>> = (0..4_u8).map(|i| vec![i, 0, SLIP_END, i]).collect();
+                let mut framer = Framer::new(framing.clone());
+                // the port was opened in the middle of a packet
+                framer.push(&[7, 7, 7]);
+                framer.push(&framing.encode(&good[0]));
+                framer.push(&framing.encode(&good[1]));
+                // a packet that lost a byte and one of the wrong length
+                let mut broken = framing.encode(&good[2]);
+                broken.remove(1);
+                framer.push(&broken);
+                framer.push(&framing.encode(&[1, 2]));
+                framer.push(&framing.encode(&good[3]));
+                let received = frames(&mut framer, 4);
+                // COBS has no leading delimiter, so the cut off start takes the first packet
+                let expected = match framing {
+                    Framing::Slip => vec![good[0].clone(), good[1].clone(), good[3].clone()],
+                    _ => vec![good[1].clone(), good[3].clone()],
+                };
+                assert_eq!(received, expected, "{:?}", framing);
+                assert_eq!(framer.malformed, 2, "{:?}", framing);
+                // bytes without a delimiter for too long are not a packet
+                framer.push(&[1; 11]);
+                assert_eq!(framer.next_frame(4), None);
+                assert_eq!(framer.malformed, 3);
+                // a delimiter starts the next packet cleanly
+                framer.push(&[delimiter(&framing)]);
+                framer.push(&framing.encode(&good[0]));
+                assert_eq!(frames(&mut framer, 4), vec![good[0].clone()]);
+            }
+        }
+        #[test]
+        fn packets_split_across_reads() {
+            let payload = [1, 0, 2, 0xC0];
+            for framing in [Framing::Cobs, Framing::Slip] {
+                let mut framer = Framer::new(framing.clone());
+                framer.push(&[delimiter(&framing)]);
+                let mut received = vec![];
+                for byte in framing.encode(&payload).repeat(3) {
+                    framer.push(&[byte]);
+                    received.extend(frames(&mut framer, 4));
+                }
+                assert_eq!(received, vec![payload.to_vec(); 3], "{:?}", framing);
+                assert_eq!(framer.malformed, 0);
+            }
+        }
+        #[test]
+        fn checksums() {
+            // the published check values over "123456789"
+            let check = b"123456789";
+            assert_eq!(Checksum::Xor.compute(check), vec![0x31]);
+            assert_eq!(Checksum::Crc8.compute(check), vec![0xF4]);
+            assert_eq!(Checksum::Crc16.compute(check), vec![0x29, 0xB1]);
+            assert!(Checksum::None.compute(check).is_empty());
+        }
+        fn formats() -> Vec<FrameFormat> {
+            let mut formats = vec![];
+            for checksum in Checksum::ALL {
+                for length_byte in [false, true] {
+                    formats.push(FrameFormat {
+                        marker: vec![0xAA, 0x55],
+                        length_byte,
+                        checksum,
+                    });
+                }
+            }
+            formats
+        }
+        #[test]
+        fn marked_frames_split_across_reads() {
+            let payloads: Vec<Vec<u8>> = (0..3_u8).map(|i| vec![i, 0xAA, 0x55, 0xAA]).collect();
+            for format in formats() {
+                let framing = Framing::Marker(format.clone());
+                let mut framer = Framer::new(framing.clone());
+                let mut received = vec![];
+                for byte in payloads.iter().flat_map(|p| framing.encode(p)) {
+                    framer.push(&[byte]);
+                    received.extend(frames(&mut framer, 4));
+                }
+                assert_eq!(received, payloads, "{:?}", format);
+                assert_eq!(framer.rejected, 0);
+            }
+        }
+        #[test]
+        fn marked_frames_resync_after_corruption() {
+            let good: Vec<Vec<u8>> = (0..4_u8).map(|i| vec![i, 10 + i, 20 + i, 30 + i]).collect();
+            for format in formats()
+                .into_iter()
+                .filter(|f| f.checksum != Checksum::None)
+            {
+                let framing = Framing::Marker(format.clone());
+                let mut framer = Framer::new(framing.clone());
+                // the tail of a frame and half a marker
+                framer.push(&[3, 2, 1, 0xAA]);
+                framer.push(&framing.encode(&good[0]));
+                // a flipped bit fails the checksum
+                let mut corrupt = framing.encode(&good[1]);
+                corrupt[3] ^= 0x04;
+                framer.push(&corrupt);
+                framer.push(&framing.encode(&good[2]));
+                // a dropped byte pulls the next frame's marker into this one
+                let mut short = framing.encode(&good[1]);
+                short.remove(4);
+                framer.push(&short);
+                framer.push(&framing.encode(&good[3]));
+                assert_eq!(
+                    frames(&mut framer, 4),
+                    vec![good[0].clone(), good[2].clone(), good[3].clone()],
+                    "{:?}",
+                    format
+                );
+                assert_eq!(framer.rejected, 2, "{:?}", format);
+            }
+        }
+        #[test]
+        fn marked_frames_check_the_length_byte() {
+            let format = FrameFormat {
+                marker: vec![0xAA, 0x55],
+                length_byte: true,
+                checksum: Checksum::Crc8,
+            };
+            let framing = Framing::Marker(format);
+            let mut framer = Framer::new(framing.clone());
+            // a frame of another size whose checksum is fine
+            framer.push(&framing.encode(&[1, 2, 3, 4, 5]));
+            framer.push(&framing.encode(&[6, 7, 8, 9]));
+            assert_eq!(frames(&mut framer, 4), vec![vec![6, 7, 8, 9]]);
+            assert_eq!(framer.rejected, 1);
+        }
+        #[test]
+        fn a_false_marker_in_a_corrupt_frame() {
+            let framing = Framing::Marker(FrameFormat::default());
+            let mut framer = Framer::new(framing.clone());
+            let mut corrupt = framing.encode(&[0xAA, 0x55, 0xAA, 0x55]);
+            corrupt[2] = 0;
+            framer.push(&corrupt);
+            framer.push(&framing.encode(&[1, 2, 3, 4]));
+            assert_eq!(frames(&mut framer, 4), vec![vec![1, 2, 3, 4]]);
+            assert!(framer.rejected >= 1);
+        }
+        #[test]
+        fn raw_frames() {
+            let mut framer = Framer::new(Framing::Raw);
+            framer.push(&[1, 2, 3]);
+            assert_eq!(framer.next_frame(2), Some(vec![1, 2]));
+            assert_eq!(framer.next_frame(2), None);
+            framer.push(&[4]);
+            assert_eq!(framer.next_frame(2), Some(vec![3, 4]));
+        }
+        #[test]
+        fn hex() {
+            assert_eq!(parse_hex("AA 55"), Some(vec![0xAA, 0x55]));
+            assert_eq!(parse_hex("0xAA55"), Some(vec![0xAA, 0x55]));
+            assert_eq!(parse_hex("0xA 0x5"), Some(vec![0xA5]));
+            assert_eq!(parse_hex("AA5"), None);
+            assert_eq!(parse_hex("GG"), None);
+            assert_eq!(parse_hex(" "), None);
+            // two bytes but one character, and a sign `from_str_radix` would take
+            assert_eq!(parse_hex("é"), None);
+            assert_eq!(parse_hex("aé1"), None);
+            assert_eq!(parse_hex("+a"), None);
+        }
+    }
+}
@@ -0,0 +1,424 @@
+pub mod generator {
+    use crate::frame::frame::Framing;
+    use crate::layout::layout::Field;
+    use crate::source::source::{Source, READ_TIMEOUT};
+    use std::io::{Read, Write};
+    use std::time::{Duration, Instant};
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Waveform {
+        Sine,
+        Square,
+        Triangle,
+        Sawtooth,
+        /// keeps rising by `amplitude` every `1 / frequency` seconds
+        Ramp,
+        WhiteNoise,
+        GaussianNoise,
+        RandomWalk,
+        /// jumps from `offset` to `offset + amplitude` after `1 / frequency` seconds
+        Step,
+    }
+    impl Waveform {
+        pub const ALL: [Waveform; 9] = [
+            Waveform::Sine,
+            Waveform::Square,
+            Waveform::Triangle,
+            Waveform::Sawtooth,
+            Waveform::Ramp,
+            Waveform::WhiteNoise,
+            Waveform::GaussianNoise,
+            Waveform::RandomWalk,
+            Waveform::Step,
+        ];
+    }
+    impl std::fmt::Display for Waveform {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Waveform::Sine => "sine",
+                    Waveform::Square => "square",
+                    Waveform::Triangle => "triangle",
+                    Waveform::Sawtooth => "sawtooth",
+                    Waveform::Ramp => "ramp",
+                    Waveform::WhiteNoise => "white noise",
+                    Waveform::GaussianNoise => "gaussian noise",
+                    Waveform::RandomWalk => "random walk",
+                    Waveform::Step => "step",
+                }
+            )
+        }
+    }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Channel {
+        pub waveform: Waveform,
+        pub amplitude: f64,
+        /// Hz, also sets how fast the random walk wanders
+        pub frequency: f64,
+        pub offset: f64,
+    }
+    impl Channel {
+        pub const AMPLITUDES: [f64; 6] = [0.1, 1.0, 10.0, 100.0, 1000.0, 30000.0];
+        pub const FREQUENCIES: [f64; 8] = [0.1, 0.5, 1.0, 2.0, 5.0, 10.0, 50.0, 100.0];
+        pub const OFFSETS: [f64; 7] = [-1000.0, -100.0, -10.0, 0.0, 10.0, 100.0, 1000.0];
+    }
+    impl Default for Channel {
+        fn default() -> Self {
+            Channel {
+                waveform: Waveform::Sine,
+                amplitude: 1.0,
+                frequency: 1.0,
+                offset: 0.0,
+            }
+        }
+    }
+    /// what the "dummy" port sends, the encoding comes from the sample type or frame layout
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Generator {
+        pub channels: Vec<Channel>,
+        /// frames per second
+        pub sample_rate: f64,
+        /// chance in percent that a frame loses one of its bytes
+        pub drop_bytes: f64,
+        /// chance in percent that a value is replaced by a spike
+        pub glitches: f64,
+    }
+    impl Generator {
+        pub const SAMPLE_RATES: [f64; 6] = [1.0, 10.0, 100.0, 1000.0, 10000.0, 100000.0];
+        pub const FAULT_RATES: [f64; 5] = [0.0, 0.1, 1.0, 5.0, 20.0];
+        /// settings of a channel, channels that were never set up are default sines
+        pub fn channel(&self, index: usize) -> Channel {
+            self.channels.get(index).copied().unwrap_or_default()
+        }
+        pub fn set_channel(&mut self, index: usize, channel: Channel) {
+            if self.channels.len() <= index {
+                self.channels.resize(index + 1, Channel::default());
+            }
+            self.channels[index] = channel;
+        }
+    }
+    impl Default for Generator {
+        fn default() -> Self {
+            Generator {
+                channels: vec![],
+                sample_rate: 1000.0,
+                drop_bytes: 0.0,
+                glitches: 0.0,
+            }
+        }
+    }
+    /// xorshift64*, good enough for noise and faults without pulling in a crate
+    #[derive(Debug)]
+    struct Rng(u64);
+    impl Rng {
+        fn new() -> Self {
+            let seed = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64);
+            Rng(seed | 1)
+        }
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        }
+        /// uniform in [0, 1)
+        fn uniform(&mut self) -> f64 {
+            (self.next() >> 11) as f64 / (1_u64 << 53) as f64
+        }
+        /// standard normal, Box-Muller
+        fn gaussian(&mut self) -> f64 {
+            let u = 1.0 - self.uniform();
+            (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * self.uniform()).cos()
+        }
+        fn chance(&mut self, percent: f64) -> bool {
+            self.uniform() * 100.0 < percent
+        }
+    }
+    /// produces frames for a layout in real time at the generator's sample rate
+    #[derive(Debug)]
+    pub struct GeneratorSource {
+        generator: Generator,
+        layout: Vec<Field>,
+        framing: Framing,
+        text: bool,
+        started: Instant,
+        produced: u64,
+        walks: Vec<f64>,
+        rng: Rng,
+        pending: Vec<u8>,
+    }
+    impl GeneratorSource {
+        /// frames made per read at most, so a fast rate can not stall the reader thread
+        const BATCH: u64 = 10000;
+        pub fn new(generator: Generator, layout: Vec<Field>, framing: Framing, text: bool) -> Self {
+            GeneratorSource {
+                generator,
+                layout,
+                framing,
+                text,
+                started: Instant::now(),
+                produced: 0,
+                walks: vec![],
+                rng: Rng::new(),
+                pending: vec![],
+            }
+        }
+        fn value(&mut self, index: usize, time: f64) -> f64 {
+            let channel = self.generator.channel(index);
+            let phase = (time * channel.frequency).fract();
+            let wave = match channel.waveform {
+                Waveform::Sine => (std::f64::consts::TAU * phase).sin(),
+                Waveform::Square => match phase < 0.5 {
+                    true => 1.0,
+                    false => -1.0,
+                },
+                Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                Waveform::Sawtooth => 2.0 * phase - 1.0,
+                Waveform::Ramp => time * channel.frequency,
+                Waveform::WhiteNoise => 2.0 * self.rng.uniform() - 1.0,
+                Waveform::GaussianNoise => self.rng.gaussian(),
+                Waveform::RandomWalk => {
+                    if self.walks.len() <= index {
+                        self.walks.resize(index + 1, 0.0);
+                    }
+                    self.walks[index] += self.rng.gaussian()
+                        * (channel.frequency / self.generator.sample_rate).sqrt();
+                    self.walks[index]
+                }
+                Waveform::Step => match time * channel.frequency >= 1.0 {
+                    true => 1.0,
+                    false => 0.0,
+                },
+            };
+            match self.rng.chance(self.generator.glitches) {
+                true => channel.offset + channel.amplitude * 10.0 * wave.signum(),
+                false => channel.offset + channel.amplitude * wave,
+            }
+        }
+        fn frame(&mut self) -> Vec<u8> {
+            let time = self.produced as f64 / self.generator.sample_rate;
+            let mut payload = vec![];
+            let mut values = vec![];
+            let mut index = 0;
+            for field in self.layout.clone() {
+                match field {
+                    Field::Value { name, converter } => {
+                        let value = self.value(index, time);
+                        index += 1;
+                        payload.extend(converter.encode(value));
+                        values.push(match name {
+                            Some(name) => format!("{}:{}", name, value),
+                            None => value.to_string(),
+                        });
+                    }
+                    Field::Padding(width) => payload.extend(vec![0; width]),
+                }
+            }
+            let mut frame = match self.text {
+                true => (values.join(",") + "\n").into_bytes(),
+                false => self.framing.encode(&payload),
+            };
+            if !frame.is_empty() && self.rng.chance(self.generator.drop_bytes) {
+                frame.remove(self.rng.next() as usize % frame.len());
+            }
+            frame
+        }
+    }
+    impl Read for GeneratorSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() {
+                let rate = self.generator.sample_rate.max(f64::MIN_POSITIVE);
+                let next = Duration::from_secs_f64((self.produced + 1) as f64 / rate);
+                let wait = next.saturating_sub(self.started.elapsed());
+                if wait > READ_TIMEOUT {
+                    std::thread::sleep(READ_TIMEOUT);
+                    return Err(std::io::ErrorKind::TimedOut.into());
+                }
+                std::thread::sleep(wait);
+                let due = (self.started.elapsed().as_secs_f64() * rate) as u64;
+                for _ in self.produced..due.min(self.produced + GeneratorSource::BATCH) {
+                    let frame = self.frame();
+                    self.pending.extend(frame);
+                    self.produced += 1;
+                }
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+    impl Write for GeneratorSource {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Source for GeneratorSource {
+        fn writable(&self) -> bool {
+            false
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::frame::frame::{Checksum, FrameFormat, Framer};
+        use crate::graph::graph::converter;
+        use crate::layout::layout::split_layout;
+        fn source(generator: &Generator, layout: Vec<Field>, framing: Framing) -> GeneratorSource {
+            let mut source = GeneratorSource::new(generator.clone(), layout, framing, false);
+            source.rng = Rng(0x2545_F491_4F6C_DD1D);
+            source
+        }
+        /// the next `n` frames, without waiting for them to be due
+        fn frames(source: &mut GeneratorSource, n: usize) -> Vec<Vec<u8>> {
+            (0..n)
+                .map(|_| {
+                    let frame = source.frame();
+                    source.produced += 1;
+                    frame
+                })
+                .collect()
+        }
+        fn values(frame: &[u8]) -> Vec<f64> {
+            frame
+                .chunks(8)
+                .map(|v| converter::be_f64.convert(v))
+                .collect()
+        }
+        fn generator(waveforms: &[Waveform], amplitude: f64, offset: f64) -> Generator {
+            Generator {
+                channels: waveforms
+                    .iter()
+                    .map(|&waveform| Channel {
+                        waveform,
+                        amplitude,
+                        frequency: 1.0,
+                        offset,
+                    })
+                    .collect(),
+                sample_rate: 8.0,
+                ..Generator::default()
+            }
+        }
+        #[test]
+        fn waveforms() {
+            use Waveform::*;
+            let waveforms = [Sine, Square, Triangle, Sawtooth, Ramp, Step];
+            let generator = generator(&waveforms, 2.0, 1.0);
+            let layout = split_layout(&[converter::be_f64; 6]);
+            let frames = frames(&mut source(&generator, layout, Framing::Raw), 9);
+            // a quarter, half and a whole period in
+            for (frame, expected) in [
+                (0, [1.0, 3.0, -1.0, -1.0, 1.0, 1.0]),
+                (2, [3.0, 3.0, 1.0, 0.0, 1.5, 1.0]),
+                (4, [1.0, -1.0, 3.0, 1.0, 2.0, 1.0]),
+                (8, [1.0, 3.0, -1.0, -1.0, 3.0, 3.0]),
+            ] {
+                for (value, expected) in values(&frames[frame]).into_iter().zip(expected) {
+                    assert!((value - expected).abs() < 1e-9, "frame {}", frame);
+                }
+            }
+        }
+        #[test]
+        fn noise() {
+            use Waveform::*;
+            let mut generator = generator(&[WhiteNoise, GaussianNoise, RandomWalk], 1.0, 0.0);
+            generator.sample_rate = 1000.0;
+            let layout = split_layout(&[converter::be_f64; 3]);
+            let frames = frames(&mut source(&generator, layout.clone(), Framing::Raw), 10000);
+            let column = |i: usize| frames.iter().map(move |frame| values(frame)[i]);
+            let mean = |i: usize| column(i).sum::<f64>() / frames.len() as f64;
+            assert!(column(0).all(|v| (-1.0..1.0).contains(&v)));
+            assert!(mean(0).abs() < 0.05);
+            assert!(mean(1).abs() < 0.05);
+            let variance = column(1).map(|v| v * v).sum::<f64>() / frames.len() as f64;
+            assert!((variance - 1.0).abs() < 0.05);
+            // the same seed gives the same noise
+            let again = self::frames(&mut source(&generator, layout, Framing::Raw), 10000);
+            assert_eq!(frames, again);
+        }
+        #[test]
+        fn fault_rates() {
+            let mut generator = generator(&[Waveform::Square], 1.0, 0.0);
+            generator.drop_bytes = 20.0;
+            let layout = split_layout(&[converter::be_f32]);
+            let frames = frames(&mut source(&generator, layout.clone(), Framing::Raw), 10000);
+            let short = frames.iter().filter(|frame| frame.len() == 3).count();
+            assert_eq!(
+                short + frames.iter().filter(|f| f.len() == 4).count(),
+                10000
+            );
+            assert!((1800..2200).contains(&short), "{} short frames", short);
+            generator.drop_bytes = 0.0;
+            generator.glitches = 5.0;
+            let frames = self::frames(&mut source(&generator, layout, Framing::Raw), 10000);
+            let spikes = frames
+                .iter()
+                .map(|frame| converter::be_f32.convert(frame).abs())
+                .filter(|value| *value == 10.0)
+                .count();
+            assert!((400..600).contains(&spikes), "{} spikes", spikes);
+        }
+        #[test]
+        fn framed_output_round_trips() {
+            let generator = generator(&[Waveform::Sine, Waveform::Sawtooth], 100.0, 0.0);
+            let layout = vec![
+                Field::Value {
+                    name: None,
+                    converter: converter::be_u16,
+                },
+                Field::Padding(1),
+                Field::Value {
+                    name: None,
+                    converter: converter::le_f32,
+                },
+            ];
+            let payloads = frames(&mut source(&generator, layout.clone(), Framing::Raw), 20);
+            for framing in [
+                Framing::Raw,
+                Framing::Marker(FrameFormat::default()),
+                Framing::Marker(FrameFormat {
+                    marker: vec![0x7E],
+                    length_byte: true,
+                    checksum: Checksum::Crc16,
+                }),
+                Framing::Cobs,
+                Framing::Slip,
+            ] {
+                let mut framer = Framer::new(framing.clone());
+                for frame in frames(&mut source(&generator, layout.clone(), framing.clone()), 20) {
+                    framer.push(&frame);
+                }
+                let decoded: Vec<_> = std::iter::from_fn(|| framer.next_frame(7)).collect();
+                // COBS packets only count after the first delimiter, SLIP ones start with one
+                let skipped = match framing {
+                    Framing::Cobs => 1,
+                    _ => 0,
+                };
+                assert_eq!(decoded, payloads[skipped..], "{:?}", framing);
+            }
+        }
+        #[test]
+        fn text_lines() {
+            let layout = vec![
+                Field::Value {
+                    name: Some("a".to_string()),
+                    converter: converter::be_f32,
+                },
+                Field::Value {
+                    name: None,
+                    converter: converter::be_f32,
+                },
+            ];
+            let generator = generator(&[Waveform::Square, Waveform::Step], 2.0, 1.0);
+            let mut source = GeneratorSource::new(generator, layout, Framing::Raw, true);
+            assert_eq!(frames(&mut source, 1), vec![b"a:3,1\n".to_vec()]);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod graph {
+    use crate::nmea::nmea::Track;
     use crate::port;
     use iced::{
         mouse,
@@ -6,31 +7,63 @@ pub mod graph {
         widget::canvas::{event, Event},
         Point, Rectangle, Renderer, Theme,
     };
+    use std::sync::Arc;
+    use std::time::UNIX_EPOCH;
     pub struct Graph {
-        pub values: Vec<[u8; 4]>,
+        /// raw samples back to back, each `converter.width()` bytes long
+        pub values: Vec<u8>,
+        /// seconds since the port was opened, one per sample
+        pub times: Vec<f64>,
         pub port: Box<dyn port::port::Port>,
         pub converter: converter,
+        pub show_errors: bool,
+        /// plot against the sample times instead of the sample index
+        pub time_axis: bool,
+        /// show the GPS track of the port instead of the values
+        pub show_track: bool,
     }
     impl Graph {
         pub fn new(port: Box<dyn port::port::Port>) -> Graph {
             Graph {
                 values: vec![],
+                times: vec![],
+                converter: port.converter(),
                 port,
-                converter: converter::be_f32,
+                show_errors: false,
+                time_axis: false,
+                show_track: false,
             }
         }
         pub fn swap_endianness(&mut self) {
             self.converter = self.converter.swap();
         }
-        pub fn push(&mut self, v: [u8; 4]) {
-            self.values.push(v);
+        pub fn push(&mut self, sample: port::port::Sample) {
+            let time = sample.time.as_secs_f64();
+            // only a replay that was rewound or looped goes back in time, start the plot over
+            if self.times.last().is_some_and(|last| time < *last) {
+                self.values.clear();
+                self.times.clear();
+            }
+            self.values.extend(sample.bytes);
+            self.times.push(time);
         }
-        pub fn get_values(&self) -> Vec<f32> {
+        pub fn get_values(&self) -> Vec<f64> {
             self.values
-                .iter()
-                .map(|x| self.converter.convert(*x))
+                .chunks_exact(self.converter.width())
+                .map(|x| self.converter.convert(x))
                 .collect()
         }
+        /// sample times as seconds since the unix epoch, or since the port was opened if the
+        /// port does not know when that was
+        pub fn get_times(&self) -> Vec<f64> {
+            let start = self
+                .port
+                .started()
+                .and_then(|started| started.duration_since(UNIX_EPOCH).ok())
+                .map(|started| started.as_secs_f64())
+                .unwrap_or(0.0);
+            self.times.iter().map(|time| start + time).collect()
+        }
     }
     impl std::fmt::Display for Graph {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
@@ -60,8 +93,24 @@ pub mod graph {
                 state.x_shift,
                 bounds.size().height + state.y_shift,
             );
-            let start = (-scale.m31 / scale.m11) as usize;
-            let end = ((bounds.size().width - scale.m31) / scale.m11) as usize + 1;
+            // x is milliseconds on the time axis, the sample index otherwise
+            let x_of = |i: usize| match self.time_axis {
+                true => self.times.get(i).map_or(0.0, |t| (t * 1000.0) as f32),
+                false => i as f32,
+            };
+            let (start, end) = match self.time_axis {
+                true => {
+                    let left = (-scale.m31 / scale.m11) as f64 / 1000.0;
+                    let right = ((bounds.size().width - scale.m31) / scale.m11) as f64 / 1000.0;
+                    let first = self.times.partition_point(|t| *t < left).saturating_sub(1);
+                    let last = self.times.partition_point(|t| *t <= right) + 1;
+                    (first, last.saturating_sub(first))
+                }
+                false => (
+                    (-scale.m31 / scale.m11) as usize,
+                    ((bounds.size().width - scale.m31) / scale.m11) as usize + 1,
+                ),
+            };
             let step_size = match bounds.size().width / (scale.m11 * 10000.0) {
                 v if v <= 1.0 => 1.0,
                 v => v,
@@ -70,15 +119,15 @@ pub mod graph {
             let bottom = (bounds.size().height - 10.0 - scale.m32) / scale.m22;
             let mut lines = canvas::path::Builder::new();
             self.values
-                .iter()
+                .chunks_exact(self.converter.width())
                 .enumerate()
                 .skip(start)
                 .take(end)
                 .step_by(step_size as usize)
                 .for_each(|(i, value)| {
                     lines.line_to(Point::new(
-                        i as f32,
-                        match self.converter.convert(*value) {
+                        x_of(i),
+                        match self.converter.convert(value) as f32 {
                             v if v > height => height,
                             v if v < bottom => bottom,
                             v => v,
@@ -131,9 +180,11 @@ pub mod graph {
                 );
                 frame.stroke(&graph_line, background_stroke);
             }
+            let left = (-scale.m31 / scale.m11).max(0.0);
+            let right = (bounds.size().width - scale.m31) / scale.m11;
             for x in 0..x_lines {
-                let value_sep = 10_f32.powi(((end - start) as f32).log10().floor() as i32);
-                let line_value = value_sep * ((start as f32 / value_sep).floor() + x as f32);
+                let value_sep = 10_f32.powi((right - left).log10().floor() as i32);
+                let line_value = value_sep * ((left / value_sep).floor() + x as f32);
                 let line_pos = (scale.m11 * line_value) + scale.m31;
                 canvas::Text {
                     color: theme.palette().primary,
@@ -203,6 +254,77 @@ pub mod graph {
             (event_status, None)
         }
     }
+    /// longitude against latitude, scaled to fit the whole track
+    pub struct TrackPlot {
+        pub track: Arc<Track>,
+    }
+    impl<Message> canvas::Program<Message> for TrackPlot {
+        type State = ();
+        fn draw(
+            &self,
+            _state: &Self::State,
+            renderer: &Renderer,
+            theme: &Theme,
+            bounds: Rectangle,
+            _cursor: mouse::Cursor,
+        ) -> Vec<canvas::Geometry> {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            let points = self.track.points();
+            let Some(&(latitude, longitude)) = points.last() else {
+                return vec![frame.into_geometry()];
+            };
+            let (mut south, mut north, mut west, mut east) =
+                (latitude, latitude, longitude, longitude);
+            for &(latitude, longitude) in points.iter() {
+                south = south.min(latitude);
+                north = north.max(latitude);
+                west = west.min(longitude);
+                east = east.max(longitude);
+            }
+            // a degree of longitude shrinks towards the poles, keep the track's shape
+            let squash = ((south + north) / 2.0).to_radians().cos();
+            let margin = 20.0;
+            let scale = ((bounds.width - 2.0 * margin) as f64 / ((east - west) * squash))
+                .min((bounds.height - 2.0 * margin) as f64 / (north - south))
+                .min(1e7);
+            let to_point = |(latitude, longitude): (f64, f64)| {
+                Point::new(
+                    bounds.width / 2.0
+                        + ((longitude - (west + east) / 2.0) * squash * scale) as f32,
+                    bounds.height / 2.0 - ((latitude - (south + north) / 2.0) * scale) as f32,
+                )
+            };
+            let mut lines = canvas::path::Builder::new();
+            for point in points.iter() {
+                lines.line_to(to_point(*point));
+            }
+            let stroke = canvas::Stroke::default()
+                .with_width(1.0)
+                .with_color(theme.palette().text);
+            frame.stroke(&lines.build(), stroke);
+            frame.fill(
+                &canvas::Path::circle(to_point((latitude, longitude)), 3.0),
+                theme.palette().primary,
+            );
+            frame.fill_text(canvas::Text {
+                color: theme.palette().primary,
+                content: format!(
+                    "{:.6}, {:.6} ({} fixes, {:.6} to {:.6} N, {:.6} to {:.6} E)",
+                    latitude,
+                    longitude,
+                    points.len(),
+                    south,
+                    north,
+                    west,
+                    east
+                ),
+                position: Point::new(1.0, 1.0),
+                size: 16.0.into(),
+                ..canvas::Text::default()
+            });
+            vec![frame.into_geometry()]
+        }
+    }
     #[derive(Debug)]
     pub struct GraphControls {
         x_scale: f32,
@@ -223,38 +345,137 @@ pub mod graph {
         }
     }
     #[allow(non_camel_case_types)]
-    #[derive(PartialEq)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
     pub enum converter {
-        be_f32,
-        le_f32,
+        u8,
+        i8,
+        be_u16,
+        le_u16,
+        be_i16,
+        le_i16,
+        be_u32,
+        le_u32,
         be_i32,
         le_i32,
+        be_f32,
+        le_f32,
+        be_u64,
+        le_u64,
+        be_i64,
+        le_i64,
+        be_f64,
+        le_f64,
         u8_to_string,
     }
+    fn bytes<const N: usize>(data: &[u8]) -> [u8; N] {
+        data[..N].try_into().expect("sample too short")
+    }
     impl converter {
-        fn convert(&self, data: [u8; 4]) -> f32 {
+        pub const ALL: [converter; 19] = [
+            converter::u8,
+            converter::i8,
+            converter::be_u16,
+            converter::le_u16,
+            converter::be_i16,
+            converter::le_i16,
+            converter::be_u32,
+            converter::le_u32,
+            converter::be_i32,
+            converter::le_i32,
+            converter::be_f32,
+            converter::le_f32,
+            converter::be_u64,
+            converter::le_u64,
+            converter::be_i64,
+            converter::le_i64,
+            converter::be_f64,
+            converter::le_f64,
+            converter::u8_to_string,
+        ];
+        /// number of bytes one sample takes on the wire
+        pub fn width(&self) -> usize {
+            match self {
+                converter::u8 | converter::i8 => 1,
+                converter::be_u16 | converter::le_u16 | converter::be_i16 | converter::le_i16 => 2,
+                converter::be_u32
+                | converter::le_u32
+                | converter::be_i32
+                | converter::le_i32
+                | converter::be_f32
+                | converter::le_f32
+                | converter::u8_to_string => 4,
+                converter::be_u64
+                | converter::le_u64
+                | converter::be_i64
+                | converter::le_i64
+                | converter::be_f64
+                | converter::le_f64 => 8,
+            }
+        }
+        pub fn convert(&self, data: &[u8]) -> f64 {
             match self {
-                converter::be_f32 => f32::from_be_bytes(data),
-                converter::le_f32 => f32::from_le_bytes(data),
-                converter::be_i32 => i32::from_be_bytes(data) as f32,
-                converter::le_i32 => i32::from_le_bytes(data) as f32,
-                converter::u8_to_string => data
-                    .into_iter()
-                    .map(|b| char::from(b))
+                converter::u8 => data[0] as f64,
+                converter::i8 => data[0] as i8 as f64,
+                converter::be_u16 => u16::from_be_bytes(bytes(data)) as f64,
+                converter::le_u16 => u16::from_le_bytes(bytes(data)) as f64,
+                converter::be_i16 => i16::from_be_bytes(bytes(data)) as f64,
+                converter::le_i16 => i16::from_le_bytes(bytes(data)) as f64,
+                converter::be_u32 => u32::from_be_bytes(bytes(data)) as f64,
+                converter::le_u32 => u32::from_le_bytes(bytes(data)) as f64,
+                converter::be_i32 => i32::from_be_bytes(bytes(data)) as f64,
+                converter::le_i32 => i32::from_le_bytes(bytes(data)) as f64,
+                converter::be_f32 => f32::from_be_bytes(bytes(data)) as f64,
+                converter::le_f32 => f32::from_le_bytes(bytes(data)) as f64,
+                converter::be_u64 => u64::from_be_bytes(bytes(data)) as f64,
+                converter::le_u64 => u64::from_le_bytes(bytes(data)) as f64,
+                converter::be_i64 => i64::from_be_bytes(bytes(data)) as f64,
+                converter::le_i64 => i64::from_le_bytes(bytes(data)) as f64,
+                converter::be_f64 => f64::from_be_bytes(bytes(data)),
+                converter::le_f64 => f64::from_le_bytes(bytes(data)),
+                converter::u8_to_string => data[..4]
+                    .iter()
+                    .map(|b| char::from(*b))
                     .collect::<String>()
-                    .parse::<f32>()
+                    .trim()
+                    .parse::<f64>()
                     .unwrap_or(0.0),
             }
         }
-        fn swap(&self) -> Self {
+        /// the bytes a device would send for `value`, the inverse of `convert`
+        pub fn encode(&self, value: f64) -> Vec<u8> {
             match self {
-                converter::be_f32 => converter::le_f32,
-                converter::le_f32 => converter::be_i32,
-                converter::be_i32 => converter::le_i32,
-                converter::le_i32 => converter::u8_to_string,
-                converter::u8_to_string => converter::be_f32,
+                converter::u8 => vec![value as u8],
+                converter::i8 => vec![value as i8 as u8],
+                converter::be_u16 => (value as u16).to_be_bytes().to_vec(),
+                converter::le_u16 => (value as u16).to_le_bytes().to_vec(),
+                converter::be_i16 => (value as i16).to_be_bytes().to_vec(),
+                converter::le_i16 => (value as i16).to_le_bytes().to_vec(),
+                converter::be_u32 => (value as u32).to_be_bytes().to_vec(),
+                converter::le_u32 => (value as u32).to_le_bytes().to_vec(),
+                converter::be_i32 => (value as i32).to_be_bytes().to_vec(),
+                converter::le_i32 => (value as i32).to_le_bytes().to_vec(),
+                converter::be_f32 => (value as f32).to_be_bytes().to_vec(),
+                converter::le_f32 => (value as f32).to_le_bytes().to_vec(),
+                converter::be_u64 => (value as u64).to_be_bytes().to_vec(),
+                converter::le_u64 => (value as u64).to_le_bytes().to_vec(),
+                converter::be_i64 => (value as i64).to_be_bytes().to_vec(),
+                converter::le_i64 => (value as i64).to_le_bytes().to_vec(),
+                converter::be_f64 => value.to_be_bytes().to_vec(),
+                converter::le_f64 => value.to_le_bytes().to_vec(),
+                converter::u8_to_string => format!("{:>4.4}", value.to_string()).into_bytes(),
             }
         }
+        /// next converter that reads the same number of bytes
+        fn swap(&self) -> Self {
+            let index = converter::ALL.iter().position(|c| c == self).unwrap_or(0);
+            converter::ALL
+                .iter()
+                .cycle()
+                .skip(index + 1)
+                .find(|c| c.width() == self.width())
+                .copied()
+                .unwrap_or(*self)
+        }
     }
     impl std::fmt::Display for converter {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
@@ -262,10 +483,24 @@ pub mod graph {
                 f,
                 "{}",
                 match self {
-                    converter::be_f32 => "be_f32",
-                    converter::le_f32 => "le_f32",
+                    converter::u8 => "u8",
+                    converter::i8 => "i8",
+                    converter::be_u16 => "be_u16",
+                    converter::le_u16 => "le_u16",
+                    converter::be_i16 => "be_i16",
+                    converter::le_i16 => "le_i16",
+                    converter::be_u32 => "be_u32",
+                    converter::le_u32 => "le_u32",
                     converter::be_i32 => "be_i32",
                     converter::le_i32 => "le_i32",
+                    converter::be_f32 => "be_f32",
+                    converter::le_f32 => "le_f32",
+                    converter::be_u64 => "be_u64",
+                    converter::le_u64 => "le_u64",
+                    converter::be_i64 => "be_i64",
+                    converter::le_i64 => "le_i64",
+                    converter::be_f64 => "be_f64",
+                    converter::le_f64 => "le_f64",
                     converter::u8_to_string => "u8_to_string",
                 }
             )
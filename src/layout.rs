@@ -0,0 +1,219 @@
+pub mod layout {
+    use crate::graph::graph::converter;
+    /// one piece of an incoming frame, either a channel or bytes to skip
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Field {
+        Value {
+            name: Option<String>,
+            converter: converter,
+        },
+        Padding(usize),
+    }
+    impl Field {
+        pub fn width(&self) -> usize {
+            match self {
+                Field::Value { converter, .. } => converter.width(),
+                Field::Padding(width) => *width,
+            }
+        }
+    }
+    /// one unnamed channel for each converter
+    pub fn split_layout(converters: &[converter]) -> Vec<Field> {
+        converters
+            .iter()
+            .map(|converter| Field::Value {
+                name: None,
+                converter: *converter,
+            })
+            .collect()
+    }
+    /// a frame has to fit in what the port reads at once
+    const MAX_WIDTH: usize = 1 << 16;
+    /// parses a Python `struct` style descriptor such as `<Ihhhf`, optionally followed by channel
+    /// names: `<Ihhhf millis,ax,ay,az,temp`. Like in Python, `@` or no prefix at all means native
+    /// byte order with every value aligned to its size, the others are packed
+    pub fn parse_layout(s: &str) -> Result<Vec<Field>, String> {
+        let (format, names) = s
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((s.trim(), ""));
+        let mut names = names
+            .split(',')
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty());
+        let mut chars = format.chars().peekable();
+        let little = match chars.peek() {
+            Some('<') => true,
+            Some('>') | Some('!') => false,
+            _ => cfg!(target_endian = "little"),
+        };
+        let aligned = !matches!(chars.peek(), Some('<' | '>' | '!' | '='));
+        if let Some('<' | '>' | '!' | '=' | '@') = chars.peek() {
+            chars.next();
+        }
+        let mut layout = vec![];
+        let mut values = 0;
+        let mut count = String::new();
+        for c in chars {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+            let repeat = match std::mem::take(&mut count) {
+                n if n.is_empty() => 1,
+                n => n.parse::<usize>().map_err(|e| e.to_string())?,
+            };
+            if repeat > MAX_WIDTH {
+                return Err(format!("frame longer than {} bytes", MAX_WIDTH));
+            }
+            if c != 'x' {
+                values += repeat;
+                if values > crate::App::MAX_SPLITS {
+                    return Err(format!(
+                        "more than {} values in one frame",
+                        crate::App::MAX_SPLITS
+                    ));
+                }
+            }
+            let converter = match (c, little) {
+                ('x', _) => {
+                    layout.push(Field::Padding(repeat));
+                    continue;
+                }
+                ('b', _) => converter::i8,
+                ('B', _) | ('?', _) => converter::u8,
+                ('h', true) => converter::le_i16,
+                ('h', false) => converter::be_i16,
+                ('H', true) => converter::le_u16,
+                ('H', false) => converter::be_u16,
+                ('i' | 'l', true) => converter::le_i32,
+                ('i' | 'l', false) => converter::be_i32,
+                ('I' | 'L', true) => converter::le_u32,
+                ('I' | 'L', false) => converter::be_u32,
+                ('q', true) => converter::le_i64,
+                ('q', false) => converter::be_i64,
+                ('Q', true) => converter::le_u64,
+                ('Q', false) => converter::be_u64,
+                ('f', true) => converter::le_f32,
+                ('f', false) => converter::be_f32,
+                ('d', true) => converter::le_f64,
+                ('d', false) => converter::be_f64,
+                (c, _) => return Err(format!("unsupported format character {:?}", c)),
+            };
+            for _ in 0..repeat {
+                let offset: usize = layout.iter().map(Field::width).sum();
+                let padding = (converter.width() - offset % converter.width()) % converter.width();
+                if aligned && padding > 0 {
+                    layout.push(Field::Padding(padding));
+                }
+                layout.push(Field::Value {
+                    name: names.next().map(|name| name.to_string()),
+                    converter,
+                });
+            }
+        }
+        if layout.iter().map(Field::width).sum::<usize>() > MAX_WIDTH {
+            return Err(format!("frame longer than {} bytes", MAX_WIDTH));
+        }
+        if !count.is_empty() {
+            return Err(format!("count {} without a format character", count));
+        }
+        if !layout.iter().any(|f| matches!(f, Field::Value { .. })) {
+            return Err("layout has no values".to_string());
+        }
+        Ok(layout)
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        fn converters(layout: &[Field]) -> Vec<Option<converter>> {
+            layout
+                .iter()
+                .map(|field| match field {
+                    Field::Value { converter, .. } => Some(*converter),
+                    Field::Padding(_) => None,
+                })
+                .collect()
+        }
+        #[test]
+        fn byte_order() {
+            use converter::*;
+            assert_eq!(
+                converters(&parse_layout("<hHiIqQfd").unwrap()),
+                [le_i16, le_u16, le_i32, le_u32, le_i64, le_u64, le_f32, le_f64].map(Some)
+            );
+            for prefix in [">", "!"] {
+                assert_eq!(
+                    converters(&parse_layout(&format!("{}hHlLqQfd", prefix)).unwrap()),
+                    [be_i16, be_u16, be_i32, be_u32, be_i64, be_u64, be_f32, be_f64].map(Some),
+                    "{:?}",
+                    prefix
+                );
+            }
+            let native = match cfg!(target_endian = "little") {
+                true => le_i16,
+                false => be_i16,
+            };
+            for prefix in ["=", "@", ""] {
+                assert_eq!(
+                    converters(&parse_layout(&format!("{}h", prefix)).unwrap()),
+                    vec![Some(native)],
+                    "{:?}",
+                    prefix
+                );
+            }
+            assert_eq!(
+                converters(&parse_layout(">bB?").unwrap()),
+                vec![Some(i8), Some(u8), Some(u8)]
+            );
+        }
+        #[test]
+        fn padding_and_counts() {
+            let layout = parse_layout("<B3xh2x2f x,temp,hum").unwrap();
+            assert_eq!(layout.len(), 6);
+            assert_eq!(layout[1], Field::Padding(3));
+            assert_eq!(layout[3], Field::Padding(2));
+            assert_eq!(layout.iter().map(Field::width).sum::<usize>(), 16);
+            let names: Vec<_> = layout
+                .iter()
+                .filter_map(|field| match field {
+                    Field::Value { name, .. } => Some(name.as_deref()),
+                    Field::Padding(_) => None,
+                })
+                .collect();
+            assert_eq!(names, vec![Some("x"), Some("temp"), Some("hum"), None]);
+        }
+        #[test]
+        fn native_alignment() {
+            let offsets = |s: &str| {
+                let mut offset = 0;
+                let mut offsets = vec![];
+                for field in parse_layout(s).unwrap() {
+                    if let Field::Value { .. } = field {
+                        offsets.push(offset);
+                    }
+                    offset += field.width();
+                }
+                (offsets, offset)
+            };
+            // like Python's struct.calcsize, with no padding after the last value
+            assert_eq!(offsets("@bhxi"), (vec![0, 2, 8], 12));
+            assert_eq!(offsets("b2hd"), (vec![0, 2, 4, 8], 16));
+            assert_eq!(offsets("=bhxi"), (vec![0, 1, 4], 8));
+            assert_eq!(offsets("<bhxi"), (vec![0, 1, 4], 8));
+        }
+        #[test]
+        fn errors() {
+            assert!(parse_layout("<hz").is_err());
+            assert!(parse_layout("<h3").is_err());
+            assert!(parse_layout("<4x").is_err());
+            assert!(parse_layout("").is_err());
+            assert!(parse_layout("99999999999999999999999h").is_err());
+            assert!(parse_layout("<16B").is_ok());
+            assert!(parse_layout("<17B").is_err());
+            assert!(parse_layout("<9h9h").is_err());
+            assert!(parse_layout("<65532x2h").is_ok());
+            assert!(parse_layout("<65533x2h").is_err());
+        }
+    }
+}
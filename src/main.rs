@@ -1,19 +1,54 @@
 use iced::{
-    time,
     widget::{
-        button, canvas, column, container, pane_grid, pane_grid::Configuration, pick_list, row,
-        slider, text, text_input, Button, Container, Space,
+        button, canvas, checkbox, column, container, pane_grid, pane_grid::Configuration,
+        pick_list, row, scrollable, slider, text, text_input, Button, Container, Space,
     },
-    Fill, Subscription,
+    window, Fill, Subscription,
 };
-use std::{fs, io::Write, time::Duration};
+use std::{fs, io::Write, sync::mpsc, time::Duration};
+// every file holds one module of the same name
+#[allow(clippy::module_inception)]
+mod console;
+#[allow(clippy::module_inception)]
+mod devices;
+#[allow(clippy::module_inception)]
+mod firmata;
+#[allow(clippy::module_inception)]
+mod frame;
+#[allow(clippy::module_inception)]
+mod generator;
+#[allow(clippy::module_inception)]
 mod graph;
+#[allow(clippy::module_inception)]
+mod layout;
+#[allow(clippy::module_inception)]
+mod modbus;
+#[allow(clippy::module_inception)]
+mod mqtt;
+#[allow(clippy::module_inception)]
+mod nmea;
+#[allow(clippy::module_inception)]
 mod port;
+#[allow(clippy::module_inception)]
+mod replay;
+#[allow(clippy::module_inception)]
+mod source;
+#[allow(clippy::module_inception)]
 mod style;
-use graph::graph::Graph;
-use port::port::from_string;
+use console::console::{ConsolePane, LineEnding};
+use devices::devices::{watch_serial_devices, Device, Names};
+use firmata::firmata::{parse_pins, Board};
+use frame::frame::{parse_hex, Checksum, FrameFormat, Framing, FramingKind};
+use generator::generator::{Channel, Generator, Waveform};
+use graph::graph::{converter, Graph, TrackPlot};
+use layout::layout::{parse_layout, split_layout};
+use modbus::modbus::{parse_registers, Poll};
+use mqtt::mqtt::{Payload, PayloadKind};
+use port::port::{from_string, Buffering, Overflow, PortError, PortHandle, PortSettings, Protocol};
+use replay::replay::{Command, Playback, Recording};
 enum Pane {
     Graph(Graph),
+    Console(ConsolePane),
     Controls,
 }
 
@@ -22,13 +57,46 @@ enum Message {
     Resize(pane_grid::ResizeEvent),
     Move(pane_grid::DragEvent),
     PathChanged(String),
-    ChangeAvlbPort(String),
+    ChangeAvlbPort(Device),
+    ChangePortName(String),
+    NamePort,
+    ChangeAddress(String),
+    AddAddress,
     ChangeOpenPort(String),
-    OpenPort(usize, usize),
+    OpenPort(usize, usize, PortSettings),
+    ChangeSettings(PortSettings),
+    ChangeProtocol(Protocol),
+    ChangeSampleType(converter),
+    ChangeSplit(usize),
+    ChangeSplitType(converter),
+    ChangeFrameLayout(String),
+    ChangeFraming(FramingKind),
+    ChangeFrameMarker(String),
+    ChangeFirmataPins(String),
+    ChangeModbusRegisters(String),
+    ChangePollInterval(Duration),
+    ChangePayload(PayloadKind),
+    ChangeJsonPath(String),
+    ChangeBuffering(Buffering),
+    ChangeFrameFormat(FrameFormat),
+    ChangeGeneratorChannel(usize),
+    ChangeGenerator(Generator),
     ClosePort(usize),
+    ClosePhysicalPort(usize),
     Split(pane_grid::Pane),
     Close(pane_grid::Pane, String),
     SwapEndianness(pane_grid::Pane),
+    ToggleErrors(pane_grid::Pane),
+    ToggleTimeAxis(pane_grid::Pane),
+    ToggleTrack(pane_grid::Pane),
+    Playback(pane_grid::Pane, Command),
+    Firmata(pane_grid::Pane, firmata::firmata::Command),
+    ChangeReplayRate(f64),
+    OpenConsole(pane_grid::Pane),
+    ConsoleInput(pane_grid::Pane, String),
+    ConsoleSend(pane_grid::Pane),
+    ConsoleLineEnding(pane_grid::Pane, LineEnding),
+    ConsoleHex(pane_grid::Pane, bool),
     ChangeNumberOfPorts(usize),
     Save(bool),
     OpenBuffer(bool),
@@ -37,13 +105,47 @@ enum Message {
 struct App {
     panes: pane_grid::State<Pane>,
     path: String,
-    avlb_ports: Vec<String>,
+    avlb_ports: Vec<Device>,
+    /// the latest list from the thread watching the serial ports
+    serial_devices: Vec<Device>,
+    serial_scans: mpsc::Receiver<Result<Vec<Device>, String>>,
+    port_names: Names,
+    port_name: String,
+    /// last failure to list serial ports, reported once
+    port_error: Option<String>,
+    address: String,
+    addresses: Vec<String>,
     open_ports: Vec<Box<dyn port::port::Port>>,
+    physical_ports: Vec<PortHandle>,
+    /// closed ports whose reader thread may still hold the device
+    closing: Vec<PortHandle>,
     avlb_port: usize,
     open_port: usize,
     internal_ports: usize,
+    port_settings: PortSettings,
+    protocol: Protocol,
+    sample_type: converter,
+    /// the sample type of every split, `sample_type` sets them all
+    split_types: Vec<converter>,
+    /// the split whose type is being picked
+    split: usize,
+    frame_layout: String,
+    framing: FramingKind,
+    frame_format: FrameFormat,
+    frame_marker: String,
+    firmata_pins: String,
+    modbus_registers: String,
+    poll_interval: Duration,
+    payload: PayloadKind,
+    json_path: String,
+    buffering: Buffering,
+    generator: Generator,
+    generator_channel: usize,
+    replay_rate: f64,
     open_delay: usize,
     status_message: String,
+    errors: mpsc::Receiver<PortError>,
+    error_sender: mpsc::Sender<PortError>,
 }
 impl Default for App {
     fn default() -> App {
@@ -51,44 +153,93 @@ impl Default for App {
     }
 }
 impl App {
+    const MAX_SPLITS: usize = 16;
     fn new() -> Self {
         let config = Configuration::Pane(Pane::Controls);
         let g_state = pane_grid::State::with_configuration(config);
         let open_ports = vec![];
-        App {
+        let (error_sender, errors) = mpsc::channel();
+        let mut app = App {
             panes: g_state,
             path: "graph1.csv".to_string(),
-            avlb_ports: get_avlb_ports(),
+            avlb_ports: vec![],
+            serial_devices: vec![],
+            serial_scans: watch_serial_devices(),
+            port_names: Names::load(),
+            port_name: String::new(),
+            port_error: None,
+            address: String::new(),
+            addresses: vec![],
             open_ports,
+            physical_ports: vec![],
+            closing: vec![],
             avlb_port: 0,
             open_port: 0,
             internal_ports: 1,
+            port_settings: PortSettings::default(),
+            protocol: Protocol::Binary,
+            sample_type: converter::be_f32,
+            split_types: vec![converter::be_f32; App::MAX_SPLITS],
+            split: 0,
+            frame_layout: String::new(),
+            framing: FramingKind::Raw,
+            frame_format: FrameFormat::default(),
+            frame_marker: "AA 55".to_string(),
+            firmata_pins: "A0 A1".to_string(),
+            modbus_registers: "1:h0".to_string(),
+            poll_interval: Duration::from_millis(100),
+            payload: PayloadKind::Number,
+            json_path: String::new(),
+            buffering: Buffering::default(),
+            generator: Generator::default(),
+            generator_channel: 0,
+            replay_rate: 1000.0,
             open_delay: 0,
             status_message: "Ready to go".to_string(),
-        }
+            errors,
+            error_sender,
+        };
+        app.refresh_ports();
+        app
+    }
+    /// lists the ports again, keeping the selected one selected by its path
+    fn refresh_ports(&mut self) {
+        let selected = self.avlb_ports.get(self.avlb_port).map(|d| d.path.clone());
+        self.avlb_ports = ["dummy", "stdin"]
+            .into_iter()
+            .chain(self.addresses.iter().map(String::as_str))
+            .map(Device::new)
+            .chain(
+                self.serial_devices
+                    .iter()
+                    .map(|device| device.clone().named(&self.port_names)),
+            )
+            .collect();
+        self.avlb_port = selected
+            .and_then(|path| self.avlb_ports.iter().position(|d| d.path == path))
+            .unwrap_or(0);
     }
-    fn view(&self) -> Container<Message> {
+    fn view(&self) -> Container<'_, Message> {
         let grid = pane_grid(&self.panes, |pane, state, _minimized| {
             let title_text: String;
             pane_grid::Content::<Message>::new(match state {
                 Pane::Graph(g) => {
-                    title_text = format!("graph: {}", g.port.name());
+                    title_text = match g.port.settings() {
+                        Some(settings) => format!("graph: {} ({})", g.port.name(), settings),
+                        None => format!("graph: {}", g.port.name()),
+                    } + &match g.port.status() {
+                        status if status.is_empty() => status,
+                        status => format!(" {}", status),
+                    };
                     graph_pane(g, pane)
                 }
+                Pane::Console(c) => {
+                    title_text = format!("console: {}", c.name);
+                    console_pane(c, pane)
+                }
                 Pane::Controls => {
                     title_text = format!("Status: {:>2}", self.status_message);
-                    controls_pane(
-                        self.avlb_ports.clone(),
-                        self.open_ports
-                            .iter()
-                            .map(|port| port.name())
-                            .collect::<Vec<String>>(),
-                        self.avlb_port,
-                        self.open_port,
-                        self.internal_ports,
-                        &self.path,
-                        pane,
-                    )
+                    controls_pane(self, pane)
                 }
             })
             .title_bar(
@@ -111,24 +262,15 @@ impl App {
             Message::Move(_) => {}
             Message::Save(is_buffer) => {
                 if is_buffer {
-                    let _ = write_buffer(
-                        self.panes
-                            .iter()
-                            .filter_map(|(_p, t)| match t {
-                                Pane::Graph(g) => Some(g),
-                                _ => None,
-                            })
-                            .collect(),
-                    );
-                    self.status_message = "Saved All Graphs to Buffer".to_string();
+                    self.status_message = match write_buffer(self.graphs()) {
+                        Ok(()) => "Saved All Graphs to Buffer".to_string(),
+                        Err(e) => format!("Could not save buffer: {}", e),
+                    };
                 } else {
                     let _ = write_file(
-                        self.panes
+                        self.graphs()
                             .iter()
-                            .filter_map(|(_p, t)| match t {
-                                Pane::Graph(g) => Some(g.get_values()),
-                                _ => None,
-                            })
+                            .map(|g| (g.get_times(), g.get_values()))
                             .collect(),
                         &self.path,
                     );
@@ -136,52 +278,238 @@ impl App {
                 }
             }
             Message::PathChanged(path) => self.path = path,
-            Message::ChangeAvlbPort(port_name) => {
-                self.avlb_ports = get_avlb_ports();
+            Message::ChangeAvlbPort(device) => {
                 self.avlb_port = self
                     .avlb_ports
                     .iter()
-                    .position(|n| n == &port_name)
+                    .position(|d| d.path == device.path)
                     .unwrap_or(0);
+                self.refresh_ports();
+                self.port_name = device.name.unwrap_or_default();
+            }
+            Message::ChangePortName(name) => self.port_name = name,
+            Message::NamePort => {
+                let device = &self.avlb_ports[self.avlb_port];
+                let Some(serial_number) = device.serial_number() else {
+                    self.status_message = format!("{} has no serial number to name", device.path);
+                    return;
+                };
+                let serial_number = serial_number.to_string();
+                match self.port_names.set(&serial_number, self.port_name.trim()) {
+                    Ok(()) => self.status_message = format!("Named device {}", serial_number),
+                    Err(e) => self.status_message = format!("Could not save port names: {}", e),
+                }
+                self.refresh_ports();
+            }
+            Message::ChangeAddress(address) => self.address = address,
+            Message::AddAddress => {
+                let address = self.address.trim().to_string();
+                if address.is_empty() {
+                    return;
+                }
+                if !self.addresses.contains(&address) {
+                    self.addresses.push(address.clone());
+                }
+                self.refresh_ports();
+                self.avlb_port = self
+                    .avlb_ports
+                    .iter()
+                    .position(|d| d.path == address)
+                    .unwrap_or(0);
+                self.address.clear();
             }
             Message::ChangeOpenPort(port_name) => {
                 self.open_port = self
-                    .open_ports
+                    .channel_names()
                     .iter()
-                    .position(|n| n.name() == port_name)
+                    .position(|n| *n == port_name)
                     .unwrap_or(0);
             }
-            Message::OpenPort(port_index, number_of_ports) => {
-                self.status_message = format!("Opening {}", self.avlb_ports[port_index]);
-                self.open_ports.append(&mut from_string(
-                    self.avlb_ports[port_index].as_str(),
-                    number_of_ports,
+            Message::OpenPort(port_index, number_of_ports, settings) => {
+                let framing = match self.framing {
+                    FramingKind::Raw => Framing::Raw,
+                    FramingKind::Marker => match parse_hex(&self.frame_marker) {
+                        Some(marker) => Framing::Marker(FrameFormat {
+                            marker,
+                            ..self.frame_format.clone()
+                        }),
+                        None => {
+                            self.status_message =
+                                format!("Bad frame marker {:?}", self.frame_marker);
+                            return;
+                        }
+                    },
+                    FramingKind::Cobs => Framing::Cobs,
+                    FramingKind::Slip => Framing::Slip,
+                };
+                let pins = match self.protocol {
+                    Protocol::Firmata => match parse_pins(&self.firmata_pins) {
+                        Ok(pins) if !pins.is_empty() => pins,
+                        Ok(_) => {
+                            self.status_message = "No Firmata pins to report".to_string();
+                            return;
+                        }
+                        Err(e) => {
+                            self.status_message = format!("Bad Firmata pins: {}", e);
+                            return;
+                        }
+                    },
+                    _ => vec![],
+                };
+                let poll = match self.protocol {
+                    Protocol::Modbus => {
+                        match parse_registers(&self.modbus_registers, self.sample_type) {
+                            Ok(registers) if !registers.is_empty() => Poll {
+                                registers,
+                                interval: self.poll_interval,
+                            },
+                            Ok(_) => {
+                                self.status_message = "No Modbus registers to poll".to_string();
+                                return;
+                            }
+                            Err(e) => {
+                                self.status_message = format!("Bad Modbus registers: {}", e);
+                                return;
+                            }
+                        }
+                    }
+                    _ => Poll::default(),
+                };
+                let payload = match self.payload {
+                    PayloadKind::Raw => Payload::Raw,
+                    PayloadKind::Number => Payload::Number,
+                    PayloadKind::Json => Payload::Json(self.json_path.trim().to_string()),
+                };
+                let fields = if self.frame_layout.trim().is_empty() {
+                    split_layout(&self.split_types[..number_of_ports])
+                } else {
+                    match parse_layout(&self.frame_layout) {
+                        Ok(fields) => fields,
+                        Err(e) => {
+                            self.status_message = format!("Bad frame layout: {}", e);
+                            return;
+                        }
+                    }
+                };
+                self.status_message = format!(
+                    "Opening {} at {}",
+                    self.avlb_ports[port_index].path, settings
+                );
+                let path = self.avlb_ports[port_index].path.as_str();
+                let previous = self
+                    .closing
+                    .iter()
+                    .position(|handle| handle.name == path)
+                    .map(|index| self.closing.remove(index));
+                let (handle, mut ports) = from_string(
+                    path,
+                    fields,
                     None,
-                ));
+                    settings,
+                    framing,
+                    self.protocol,
+                    &pins,
+                    &poll,
+                    &payload,
+                    &self.generator,
+                    self.buffering,
+                    previous,
+                    self.error_sender.clone(),
+                );
+                self.physical_ports.push(handle);
+                self.open_ports.append(&mut ports);
                 if self.avlb_port >= self.avlb_ports.len() {
                     self.avlb_port = 0
                 }
             }
+            Message::ChangeSettings(settings) => self.port_settings = settings,
+            Message::ChangeProtocol(protocol) => self.protocol = protocol,
+            Message::ChangeSampleType(sample_type) => {
+                self.sample_type = sample_type;
+                self.split_types.fill(sample_type);
+            }
+            Message::ChangeSplit(split) => self.split = split,
+            Message::ChangeSplitType(sample_type) => self.split_types[self.split] = sample_type,
+            Message::ChangeFrameLayout(frame_layout) => self.frame_layout = frame_layout,
+            Message::ChangeFraming(framing) => self.framing = framing,
+            Message::ChangeFrameMarker(marker) => self.frame_marker = marker,
+            Message::ChangeFirmataPins(pins) => self.firmata_pins = pins,
+            Message::ChangeModbusRegisters(registers) => self.modbus_registers = registers,
+            Message::ChangePollInterval(interval) => self.poll_interval = interval,
+            Message::ChangePayload(payload) => self.payload = payload,
+            Message::ChangeJsonPath(path) => self.json_path = path,
+            Message::ChangeBuffering(buffering) => self.buffering = buffering,
+            Message::ChangeFrameFormat(format) => self.frame_format = format,
+            Message::ChangeGeneratorChannel(channel) => self.generator_channel = channel,
+            Message::ChangeGenerator(generator) => self.generator = generator,
             Message::ClosePort(port_index) => {
-                if self.open_port == 0 {
-                    return;
-                }
-                self.open_ports.remove(port_index);
-                if self.open_port >= self.open_ports.len() {
-                    self.open_port = 0
+                if let Some(id) = self
+                    .channels()
+                    .get(port_index)
+                    .and_then(|port| port.physical_port())
+                {
+                    self.close_physical_port(id)
                 }
             }
+            Message::ClosePhysicalPort(id) => self.close_physical_port(id),
             Message::Split(pane) => {
-                if self.open_port >= self.open_ports.len() {
-                    return;
-                }
+                // channels that are already shown get another subscriber instead of moving
+                let port = if self.open_port < self.open_ports.len() {
+                    self.open_ports.remove(self.open_port)
+                } else {
+                    match self
+                        .channels()
+                        .get(self.open_port)
+                        .and_then(|port| port.subscribe())
+                    {
+                        Some(port) => port,
+                        None => return,
+                    }
+                };
                 self.panes.split(
                     pane_grid::Axis::Horizontal,
                     pane,
-                    Pane::Graph(Graph::new(self.open_ports.remove(self.open_port))),
+                    Pane::Graph(Graph::new(port)),
                 );
                 self.open_delay = 10;
             }
+            Message::OpenConsole(pane) => {
+                let port = match self.panes.get(pane) {
+                    Some(Pane::Graph(graph)) => graph.port.as_ref(),
+                    _ => match self.channels().get(self.open_port) {
+                        Some(port) => *port,
+                        None => return,
+                    },
+                };
+                let Some(console) = port.console() else {
+                    self.status_message = format!("{} can not be written to", port.name());
+                    return;
+                };
+                let console = Pane::Console(ConsolePane::new(port.name(), console));
+                self.panes.split(pane_grid::Axis::Horizontal, pane, console);
+            }
+            Message::ConsoleInput(pane, input) => {
+                if let Some(Pane::Console(console)) = self.panes.get_mut(pane) {
+                    console.input = input
+                }
+            }
+            Message::ConsoleSend(pane) => {
+                if let Some(Pane::Console(console)) = self.panes.get_mut(pane) {
+                    if let Err(e) = console.send() {
+                        self.status_message = e
+                    }
+                }
+            }
+            Message::ConsoleLineEnding(pane, line_ending) => {
+                if let Some(Pane::Console(console)) = self.panes.get_mut(pane) {
+                    console.line_ending = line_ending
+                }
+            }
+            Message::ConsoleHex(pane, hex) => {
+                if let Some(Pane::Console(console)) = self.panes.get_mut(pane) {
+                    console.hex = hex
+                }
+            }
             Message::Close(pane, graph) => {
                 self.status_message = format!("Closed {}", graph);
                 self.panes.close(pane);
@@ -190,28 +518,112 @@ impl App {
                 Some(Pane::Graph(graph)) => graph.swap_endianness(),
                 _ => unimplemented!(),
             },
-            Message::ChangeNumberOfPorts(internal_ports) => self.internal_ports = internal_ports,
-            Message::OpenBuffer(from_dir) => {
-                let file = if from_dir {
-                    fs::File::open(self.path.clone())
-                } else {
-                    fs::File::open(".buffer")
+            Message::ToggleErrors(pane) => {
+                if let Some(Pane::Graph(graph)) = self.panes.get_mut(pane) {
+                    graph.show_errors = !graph.show_errors
                 }
-                .expect("no buffer");
-                for buf_port in from_string("buffer", self.internal_ports, Some(file)) {
-                    self.open_ports.push(buf_port);
+            }
+            Message::ToggleTimeAxis(pane) => {
+                if let Some(Pane::Graph(graph)) = self.panes.get_mut(pane) {
+                    graph.time_axis = !graph.time_axis
+                }
+            }
+            Message::ToggleTrack(pane) => {
+                if let Some(Pane::Graph(graph)) = self.panes.get_mut(pane) {
+                    graph.show_track = !graph.show_track
+                }
+            }
+            Message::Playback(pane, command) => {
+                if let Some(Pane::Graph(graph)) = self.panes.get(pane) {
+                    if let Some(playback) = graph.port.playback() {
+                        playback.apply(command)
+                    }
+                }
+            }
+            Message::Firmata(pane, command) => {
+                if let Some(Pane::Graph(graph)) = self.panes.get(pane) {
+                    if let Some(board) = graph.port.board() {
+                        board.apply(command)
+                    }
                 }
+            }
+            Message::ChangeReplayRate(replay_rate) => self.replay_rate = replay_rate,
+            Message::ChangeNumberOfPorts(internal_ports) => {
+                self.internal_ports = internal_ports;
+                self.split = self.split.min(internal_ports - 1);
+            }
+            Message::OpenBuffer(from_dir) => {
+                let path = if from_dir {
+                    self.path.as_str()
+                } else {
+                    ".buffer"
+                };
+                let recording = match Recording::open(path, self.replay_rate) {
+                    Ok(recording) => recording,
+                    Err(e) => {
+                        self.status_message = format!("Could not open buffer: {}", e);
+                        return;
+                    }
+                };
+                let (handle, mut ports) = from_string(
+                    "buffer",
+                    split_layout(&self.split_types[..self.internal_ports]),
+                    Some(recording),
+                    self.port_settings,
+                    Framing::Raw,
+                    Protocol::Binary,
+                    &[],
+                    &Poll::default(),
+                    &Payload::Raw,
+                    &self.generator,
+                    self.buffering,
+                    None,
+                    self.error_sender.clone(),
+                );
+                self.physical_ports.push(handle);
+                self.open_ports.append(&mut ports);
                 self.status_message = "Open Graph From buffer".to_string();
                 self.open_delay = 10;
             }
             Message::Update => {
+                while let Ok(error) = self.errors.try_recv() {
+                    self.status_message = format!("{}: {}", error.port, error.message);
+                }
+                if let Some(scan) = self.serial_scans.try_iter().last() {
+                    match scan {
+                        Ok(devices) => {
+                            self.serial_devices = devices;
+                            self.port_error = None;
+                        }
+                        Err(e) => {
+                            self.serial_devices = vec![];
+                            let message = format!("Could not list serial ports: {}", e);
+                            if self.port_error.as_ref() != Some(&message) {
+                                self.status_message = message.clone();
+                                self.port_error = Some(message);
+                            }
+                        }
+                    }
+                    self.refresh_ports();
+                }
+                for handle in &self.physical_ports {
+                    self.open_ports.append(&mut handle.take_channels());
+                }
+                // threads also stop on their own once every channel of them was closed
+                self.physical_ports.retain(|handle| handle.running());
+                self.closing.retain(|handle| handle.running());
+                for (_, pane) in self.panes.iter_mut() {
+                    if let Pane::Console(console) = pane {
+                        console.update()
+                    }
+                }
                 if self.open_delay == 0 {
                     let _: Vec<_> = self
                         .panes
                         .iter_mut()
                         .map(|(_, t)| {
                             if let Pane::Graph(g) = t {
-                                if let Some(v) = g.port.next() {
+                                while let Some(v) = g.port.next() {
                                     g.push(v)
                                 }
                             }
@@ -223,8 +635,68 @@ impl App {
             }
         }
     }
+    /// the graphs in the order they were opened, the pane grid keeps them in a hash map
+    fn graphs(&self) -> Vec<&Graph> {
+        let mut graphs: Vec<_> = self
+            .panes
+            .iter()
+            .filter_map(|(pane, state)| match state {
+                Pane::Graph(g) => Some((*pane, g)),
+                _ => None,
+            })
+            .collect();
+        graphs.sort_by_key(|(pane, _)| *pane);
+        graphs.into_iter().map(|(_, g)| g).collect()
+    }
+    /// channels waiting for a graph followed by those already shown in one
+    fn channels(&self) -> Vec<&dyn port::port::Port> {
+        self.open_ports
+            .iter()
+            .map(|port| port.as_ref())
+            .chain(self.graphs().into_iter().map(|g| g.port.as_ref()))
+            .collect()
+    }
+    /// what the open ports list shows for `channels`
+    fn channel_names(&self) -> Vec<String> {
+        let waiting = self.open_ports.len();
+        self.channels()
+            .iter()
+            .enumerate()
+            .map(|(index, port)| match index < waiting {
+                true => port.name(),
+                false => format!("{} (shown, add another view)", port.name()),
+            })
+            .collect()
+    }
+    /// stops the reader thread of a physical port, then drops all of its channels and graphs
+    fn close_physical_port(&mut self, id: usize) {
+        let Some(index) = self.physical_ports.iter().position(|h| h.id() == id) else {
+            return;
+        };
+        let handle = self.physical_ports.remove(index);
+        handle.close();
+        self.open_ports
+            .retain(|port| port.physical_port() != Some(id));
+        if self.open_port >= self.open_ports.len() {
+            self.open_port = 0
+        }
+        let graphs: Vec<pane_grid::Pane> = self
+            .panes
+            .iter()
+            .filter_map(|(pane, state)| match state {
+                Pane::Graph(g) if g.port.physical_port() == Some(id) => Some(*pane),
+                _ => None,
+            })
+            .collect();
+        for pane in graphs {
+            self.panes.close(pane);
+        }
+        self.status_message = format!("Closed {}", handle.name);
+        self.closing.push(handle);
+    }
     fn subscription(&self) -> Subscription<Message> {
-        time::every(Duration::from_micros(100)).map(|_| Message::Update)
+        // everything that arrived since the last frame is drawn at once, however fast the port
+        window::frames().map(|_| Message::Update)
     }
 }
 const LINE_HEIGHT: f32 = 1.6;
@@ -232,15 +704,13 @@ const TEXT_SIZE: f32 = 16.0;
 const UNIT_WIDTH: f32 = 160.0;
 const ROW_SPACING: f32 = 8.0;
 const ROW_HEIGHT: f32 = 32.0;
-fn controls_pane<'a>(
-    avlb_ports: Vec<String>,
-    open_ports: Vec<String>,
-    current_avlb_port: usize,
-    current_open_port: usize,
-    internal_ports: usize,
-    path: &str,
-    pane: pane_grid::Pane,
-) -> Container<'a, Message> {
+fn controls_pane(app: &App, pane: pane_grid::Pane) -> Container<'_, Message> {
+    let avlb_ports = app.avlb_ports.clone();
+    let open_ports = app.channel_names();
+    let current_avlb_port = app.avlb_port;
+    let current_open_port = app.open_port;
+    let internal_ports = app.internal_ports;
+    let settings = app.port_settings;
     let avlb_port = avlb_ports[current_avlb_port].clone();
     let open_port = open_ports
         .get(current_open_port)
@@ -248,7 +718,7 @@ fn controls_pane<'a>(
     container(
         column![
             row![
-                controls_pane_button(Box::leak::<'a>(
+                controls_pane_button(Box::leak(
                     format!(
                         "Open {} Port{}",
                         internal_ports,
@@ -256,10 +726,21 @@ fn controls_pane<'a>(
                     )
                     .into_boxed_str()
                 ))
-                .on_press(Message::OpenPort(current_avlb_port, internal_ports)),
+                .on_press(Message::OpenPort(
+                    current_avlb_port,
+                    internal_ports,
+                    settings
+                )),
                 controls_pane_button("New Graph").on_press(Message::Split(pane)),
+                controls_pane_button("New Console").on_press(Message::OpenConsole(pane)),
                 controls_pane_button("Close Port").on_press(Message::ClosePort(current_open_port)),
                 Space::with_width(Fill),
+                text_input("Name this device", &app.port_name)
+                    .on_input(Message::ChangePortName)
+                    .on_submit(Message::NamePort)
+                    .line_height(LINE_HEIGHT)
+                    .size(TEXT_SIZE)
+                    .width(UNIT_WIDTH),
                 pick_list(avlb_ports, Some(avlb_port), Message::ChangeAvlbPort)
                     .text_line_height(LINE_HEIGHT)
                     .text_size(TEXT_SIZE)
@@ -269,12 +750,45 @@ fn controls_pane<'a>(
             .spacing(ROW_SPACING)
             .align_y(iced::alignment::Vertical::Center),
             row![
-                slider(1_f32..=16_f32, internal_ports as f32, |x| {
+                slider(1_f32..=App::MAX_SPLITS as f32, internal_ports as f32, |x| {
                     Message::ChangeNumberOfPorts(x as usize)
                 })
                 .width(UNIT_WIDTH * 2.0 + ROW_SPACING),
                 controls_pane_button("Open Bin:").on_press(Message::OpenBuffer(true)),
+                pick_list(
+                    (1..=internal_ports)
+                        .map(|i| format!("split {}", i))
+                        .collect::<Vec<String>>(),
+                    Some(format!("split {}", app.split + 1)),
+                    |split: String| Message::ChangeSplit(
+                        split
+                            .trim_start_matches("split ")
+                            .parse::<usize>()
+                            .unwrap_or(1)
+                            - 1
+                    )
+                )
+                .text_line_height(LINE_HEIGHT)
+                .text_size(TEXT_SIZE)
+                .width(UNIT_WIDTH * 0.75),
+                pick_list(
+                    converter::ALL,
+                    app.split_types.get(app.split),
+                    Message::ChangeSplitType
+                )
+                .text_line_height(LINE_HEIGHT)
+                .text_size(TEXT_SIZE)
+                .width(UNIT_WIDTH),
                 Space::with_width(Fill),
+                text_input(
+                    "tcp://host:port, udp://bind:port or file://path",
+                    &app.address
+                )
+                .on_input(Message::ChangeAddress)
+                .on_submit(Message::AddAddress)
+                .line_height(LINE_HEIGHT)
+                .size(TEXT_SIZE)
+                .width(UNIT_WIDTH * 2.0),
                 pick_list(open_ports, Some(open_port), Message::ChangeOpenPort)
                     .text_line_height(LINE_HEIGHT)
                     .text_size(TEXT_SIZE)
@@ -284,7 +798,7 @@ fn controls_pane<'a>(
             .spacing(ROW_SPACING)
             .align_y(iced::alignment::Vertical::Center),
             row![
-                controls_pane_button(Box::leak::<'a>(
+                controls_pane_button(Box::leak(
                     format!(
                         "Open {} Buffer{}",
                         internal_ports,
@@ -295,8 +809,22 @@ fn controls_pane<'a>(
                 .on_press(Message::OpenBuffer(false)),
                 controls_pane_button("Save to Buffer").on_press(Message::Save(true)),
                 controls_pane_button("Save to:").on_press(Message::Save(false)),
+                pick_list(
+                    Recording::SAMPLE_RATES.map(|v| format!("replay {} S/s", v)),
+                    Some(format!("replay {} S/s", app.replay_rate)),
+                    |replay_rate: String| Message::ChangeReplayRate(
+                        replay_rate
+                            .trim_start_matches("replay ")
+                            .trim_end_matches(" S/s")
+                            .parse()
+                            .unwrap_or(1000.0)
+                    )
+                )
+                .text_line_height(LINE_HEIGHT)
+                .text_size(TEXT_SIZE)
+                .width(UNIT_WIDTH),
                 Space::with_width(Fill),
-                text_input("Path", &path)
+                text_input("Path", &app.path)
                     .on_input(Message::PathChanged)
                     // .on_submit(Message::Save(false))
                     .line_height(LINE_HEIGHT)
@@ -306,6 +834,72 @@ fn controls_pane<'a>(
             .height(ROW_HEIGHT)
             .spacing(ROW_SPACING)
             .align_y(iced::alignment::Vertical::Center),
+            port_settings_row(settings),
+            protocol_row(app),
+            generator_rows(app),
+            physical_ports_list(app),
+            row![
+                text_input(
+                    "Frame layout, e.g. <Ihhhf millis,ax,ay,az,temp (overrides sample type and splits)",
+                    &app.frame_layout
+                )
+                .on_input(Message::ChangeFrameLayout)
+                .line_height(LINE_HEIGHT)
+                .size(TEXT_SIZE)
+                .width(Fill),
+                text_input("Firmata pins, e.g. A0 A1 D2", &app.firmata_pins)
+                    .on_input(Message::ChangeFirmataPins)
+                    .line_height(LINE_HEIGHT)
+                    .size(TEXT_SIZE)
+                    .width(UNIT_WIDTH * 2.0),
+            ]
+            .height(ROW_HEIGHT)
+            .spacing(ROW_SPACING)
+            .align_y(iced::alignment::Vertical::Center),
+            row![
+                text_input(
+                    "Modbus registers as slave:register[:type], e.g. 1:h0 1:i10:le_u16 (h holding, i input)",
+                    &app.modbus_registers
+                )
+                .on_input(Message::ChangeModbusRegisters)
+                .line_height(LINE_HEIGHT)
+                .size(TEXT_SIZE)
+                .width(Fill),
+                pick_list(
+                    Poll::INTERVALS.map(|ms| format!("poll every {} ms", ms)),
+                    Some(format!("poll every {} ms", app.poll_interval.as_millis())),
+                    |interval: String| Message::ChangePollInterval(Duration::from_millis(
+                        interval
+                            .trim_start_matches("poll every ")
+                            .trim_end_matches(" ms")
+                            .parse()
+                            .unwrap_or(100)
+                    ))
+                )
+                .text_line_height(LINE_HEIGHT)
+                .text_size(TEXT_SIZE)
+                .width(UNIT_WIDTH * 2.0),
+            ]
+            .height(ROW_HEIGHT)
+            .spacing(ROW_SPACING)
+            .align_y(iced::alignment::Vertical::Center),
+            row![
+                pick_list(PayloadKind::ALL, Some(app.payload), Message::ChangePayload)
+                    .text_line_height(LINE_HEIGHT)
+                    .text_size(TEXT_SIZE)
+                    .width(UNIT_WIDTH * 2.0),
+                text_input(
+                    "JSON path of the value, e.g. sensor.values.0 (for mqtt://host/topic/# addresses)",
+                    &app.json_path
+                )
+                .on_input(Message::ChangeJsonPath)
+                .line_height(LINE_HEIGHT)
+                .size(TEXT_SIZE)
+                .width(Fill),
+            ]
+            .height(ROW_HEIGHT)
+            .spacing(ROW_SPACING)
+            .align_y(iced::alignment::Vertical::Center),
         ]
         .spacing(8.0), //column spacing
     )
@@ -314,6 +908,337 @@ fn controls_pane<'a>(
     .height(Fill)
     .padding(ROW_SPACING)
 }
+fn port_settings_row<'a>(settings: PortSettings) -> iced::widget::Row<'a, Message> {
+    row![
+        pick_list(
+            PortSettings::BAUD_RATES,
+            Some(settings.baud_rate),
+            move |baud_rate| Message::ChangeSettings(PortSettings {
+                baud_rate,
+                ..settings
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            [
+                serialport::DataBits::Five,
+                serialport::DataBits::Six,
+                serialport::DataBits::Seven,
+                serialport::DataBits::Eight,
+            ],
+            Some(settings.data_bits),
+            move |data_bits| Message::ChangeSettings(PortSettings {
+                data_bits,
+                ..settings
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            [
+                serialport::Parity::None,
+                serialport::Parity::Odd,
+                serialport::Parity::Even,
+            ],
+            Some(settings.parity),
+            move |parity| Message::ChangeSettings(PortSettings { parity, ..settings })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            [serialport::StopBits::One, serialport::StopBits::Two],
+            Some(settings.stop_bits),
+            move |stop_bits| Message::ChangeSettings(PortSettings {
+                stop_bits,
+                ..settings
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            [
+                serialport::FlowControl::None,
+                serialport::FlowControl::Software,
+                serialport::FlowControl::Hardware,
+            ],
+            Some(settings.flow_control),
+            move |flow_control| Message::ChangeSettings(PortSettings {
+                flow_control,
+                ..settings
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            PortSettings::TIMEOUTS.map(|ms| format!("{} ms timeout", ms)),
+            Some(format!("{} ms timeout", settings.timeout.as_millis())),
+            move |timeout: String| Message::ChangeSettings(PortSettings {
+                timeout: Duration::from_millis(
+                    timeout.trim_end_matches(" ms timeout").parse().unwrap_or(0)
+                ),
+                ..settings
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+    ]
+    .height(ROW_HEIGHT)
+    .spacing(ROW_SPACING)
+    .align_y(iced::alignment::Vertical::Center)
+}
+/// every open physical port with the channels split off it, those in a graph are marked
+fn physical_ports_list(app: &App) -> iced::widget::Column<'_, Message> {
+    column(app.physical_ports.iter().map(|handle| {
+        let id = Some(handle.id());
+        let splits = app
+            .open_ports
+            .iter()
+            .filter(|port| port.physical_port() == id)
+            .map(|port| port.name())
+            .chain(
+                app.graphs()
+                    .into_iter()
+                    .filter(|g| g.port.physical_port() == id)
+                    .map(|g| format!("{} (graph)", g.port.name())),
+            )
+            .collect::<Vec<String>>()
+            .join(", ");
+        let name = match handle.settings {
+            Some(settings) => format!("{} ({}) {}", handle.name, settings, handle.status()),
+            None => format!("{} {}", handle.name, handle.status()),
+        };
+        row![
+            text(name)
+                .line_height(LINE_HEIGHT)
+                .size(TEXT_SIZE)
+                .width(UNIT_WIDTH * 2.0),
+            text(splits)
+                .line_height(LINE_HEIGHT)
+                .size(TEXT_SIZE)
+                .width(Fill),
+            controls_pane_button("Close").on_press(Message::ClosePhysicalPort(handle.id())),
+        ]
+        .spacing(ROW_SPACING)
+        .align_y(iced::alignment::Vertical::Center)
+        .into()
+    }))
+    .spacing(ROW_SPACING)
+}
+/// what the dummy port sends, one channel at a time
+fn generator_rows(app: &App) -> iced::widget::Column<'_, Message> {
+    let index = app.generator_channel;
+    let channel = app.generator.channel(index);
+    let with_channel = move |channel: Channel| {
+        let mut generator = app.generator.clone();
+        generator.set_channel(index, channel);
+        Message::ChangeGenerator(generator)
+    };
+    let channels = row![
+        text("Dummy:")
+            .line_height(LINE_HEIGHT)
+            .size(TEXT_SIZE)
+            .width(UNIT_WIDTH / 2.0),
+        pick_list(
+            (1..=16)
+                .map(|i| format!("channel {}", i))
+                .collect::<Vec<String>>(),
+            Some(format!("channel {}", index + 1)),
+            |channel: String| Message::ChangeGeneratorChannel(
+                channel
+                    .trim_start_matches("channel ")
+                    .parse::<usize>()
+                    .unwrap_or(1)
+                    - 1
+            )
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH * 0.75),
+        pick_list(Waveform::ALL, Some(channel.waveform), move |waveform| {
+            with_channel(Channel {
+                waveform,
+                ..channel
+            })
+        })
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            Channel::AMPLITUDES.map(|v| format!("amplitude {}", v)),
+            Some(format!("amplitude {}", channel.amplitude)),
+            move |amplitude: String| with_channel(Channel {
+                amplitude: amplitude
+                    .trim_start_matches("amplitude ")
+                    .parse()
+                    .unwrap_or(1.0),
+                ..channel
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            Channel::FREQUENCIES.map(|v| format!("{} Hz", v)),
+            Some(format!("{} Hz", channel.frequency)),
+            move |frequency: String| with_channel(Channel {
+                frequency: frequency.trim_end_matches(" Hz").parse().unwrap_or(1.0),
+                ..channel
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH * 0.75),
+        pick_list(
+            Channel::OFFSETS.map(|v| format!("offset {}", v)),
+            Some(format!("offset {}", channel.offset)),
+            move |offset: String| with_channel(Channel {
+                offset: offset.trim_start_matches("offset ").parse().unwrap_or(0.0),
+                ..channel
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+    ]
+    .height(ROW_HEIGHT)
+    .spacing(ROW_SPACING)
+    .align_y(iced::alignment::Vertical::Center);
+    let rates = row![
+        Space::with_width(UNIT_WIDTH / 2.0),
+        pick_list(
+            Generator::SAMPLE_RATES.map(|v| format!("{} S/s", v)),
+            Some(format!("{} S/s", app.generator.sample_rate)),
+            |sample_rate: String| Message::ChangeGenerator(Generator {
+                sample_rate: sample_rate
+                    .trim_end_matches(" S/s")
+                    .parse()
+                    .unwrap_or(1000.0),
+                ..app.generator.clone()
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            Generator::FAULT_RATES.map(|v| format!("{}% dropped bytes", v)),
+            Some(format!("{}% dropped bytes", app.generator.drop_bytes)),
+            |drop_bytes: String| Message::ChangeGenerator(Generator {
+                drop_bytes: drop_bytes
+                    .trim_end_matches("% dropped bytes")
+                    .parse()
+                    .unwrap_or(0.0),
+                ..app.generator.clone()
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH * 1.25),
+        pick_list(
+            Generator::FAULT_RATES.map(|v| format!("{}% glitches", v)),
+            Some(format!("{}% glitches", app.generator.glitches)),
+            |glitches: String| Message::ChangeGenerator(Generator {
+                glitches: glitches
+                    .trim_end_matches("% glitches")
+                    .parse()
+                    .unwrap_or(0.0),
+                ..app.generator.clone()
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+    ]
+    .height(ROW_HEIGHT)
+    .spacing(ROW_SPACING)
+    .align_y(iced::alignment::Vertical::Center);
+    column![channels, rates].spacing(8.0)
+}
+fn protocol_row(app: &App) -> iced::widget::Row<'_, Message> {
+    let format = app.frame_format.clone();
+    let buffering = app.buffering;
+    row![
+        pick_list(Protocol::ALL, Some(app.protocol), Message::ChangeProtocol)
+            .text_line_height(LINE_HEIGHT)
+            .text_size(TEXT_SIZE)
+            .width(UNIT_WIDTH),
+        pick_list(
+            converter::ALL,
+            Some(app.sample_type),
+            Message::ChangeSampleType
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(FramingKind::ALL, Some(app.framing), Message::ChangeFraming)
+            .text_line_height(LINE_HEIGHT)
+            .text_size(TEXT_SIZE)
+            .width(UNIT_WIDTH),
+        text_input("Marker (hex)", &app.frame_marker)
+            .on_input(Message::ChangeFrameMarker)
+            .line_height(LINE_HEIGHT)
+            .size(TEXT_SIZE)
+            .width(UNIT_WIDTH),
+        checkbox("Length byte", format.length_byte)
+            .on_toggle({
+                let format = format.clone();
+                move |length_byte| {
+                    Message::ChangeFrameFormat(FrameFormat {
+                        length_byte,
+                        ..format.clone()
+                    })
+                }
+            })
+            .text_line_height(LINE_HEIGHT)
+            .text_size(TEXT_SIZE)
+            .width(UNIT_WIDTH),
+        pick_list(Checksum::ALL, Some(format.checksum), move |checksum| {
+            Message::ChangeFrameFormat(FrameFormat {
+                checksum,
+                ..format.clone()
+            })
+        })
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            Buffering::CAPACITIES.map(|v| format!("buffer {}", v)),
+            Some(format!("buffer {}", app.buffering.capacity)),
+            move |capacity: String| Message::ChangeBuffering(Buffering {
+                capacity: capacity
+                    .trim_start_matches("buffer ")
+                    .parse()
+                    .unwrap_or(100_000),
+                ..buffering
+            })
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+        pick_list(
+            Overflow::ALL,
+            Some(app.buffering.overflow),
+            move |overflow| {
+                Message::ChangeBuffering(Buffering {
+                    overflow,
+                    ..buffering
+                })
+            }
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH),
+    ]
+    .height(ROW_HEIGHT)
+    .spacing(ROW_SPACING)
+    .align_y(iced::alignment::Vertical::Center)
+}
 fn controls_pane_button<'a>(contents: &'a str) -> Button<'a, Message> {
     button(
         text(contents)
@@ -323,10 +1248,29 @@ fn controls_pane_button<'a>(contents: &'a str) -> Button<'a, Message> {
     )
     .width(UNIT_WIDTH)
 }
-fn graph_pane(graph: &Graph, pane: pane_grid::Pane) -> Container<Message> {
+fn graph_pane(graph: &Graph, pane: pane_grid::Pane) -> Container<'_, Message> {
+    let errors = graph.port.errors();
+    let error_count = errors.len();
+    let body: iced::Element<Message> = if graph.show_errors {
+        scrollable(
+            column(
+                errors
+                    .into_iter()
+                    .rev()
+                    .map(|error| text(error).size(TEXT_SIZE).into()),
+            )
+            .width(Fill),
+        )
+        .height(Fill)
+        .into()
+    } else if let Some(track) = graph.port.track().filter(|_| graph.show_track) {
+        canvas(TrackPlot { track }).width(Fill).height(Fill).into()
+    } else {
+        canvas(graph).width(Fill).height(Fill).into()
+    };
     container(
         column![
-            canvas(graph).width(Fill).height(Fill),
+            body,
             row![
                 button(
                     text("Close Pane")
@@ -343,49 +1287,262 @@ fn graph_pane(graph: &Graph, pane: pane_grid::Pane) -> Container<Message> {
                         .center(),
                 )
                 .width(UNIT_WIDTH * 2.0)
-                .on_press(Message::SwapEndianness(pane))
+                .on_press(Message::SwapEndianness(pane)),
+                button(
+                    text(if graph.show_errors {
+                        "Show Graph".to_string()
+                    } else {
+                        format!("Errors ({})", error_count)
+                    })
+                    .line_height(LINE_HEIGHT)
+                    .size(TEXT_SIZE)
+                    .center(),
+                )
+                .width(UNIT_WIDTH)
+                .on_press(Message::ToggleErrors(pane)),
+                button(
+                    text(if graph.time_axis {
+                        "By Index"
+                    } else {
+                        "By Time"
+                    })
+                    .line_height(LINE_HEIGHT)
+                    .size(TEXT_SIZE)
+                    .center(),
+                )
+                .width(UNIT_WIDTH)
+                .on_press(Message::ToggleTimeAxis(pane)),
+                button(
+                    text("Console")
+                        .line_height(LINE_HEIGHT)
+                        .size(TEXT_SIZE)
+                        .center(),
+                )
+                .width(UNIT_WIDTH)
+                .on_press_maybe(graph.port.console().map(|_| Message::OpenConsole(pane)))
             ]
+            .push_maybe(graph.port.track().map(|_| {
+                button(
+                    text(if graph.show_track { "Values" } else { "Track" })
+                        .line_height(LINE_HEIGHT)
+                        .size(TEXT_SIZE)
+                        .center(),
+                )
+                .width(UNIT_WIDTH)
+                .on_press(Message::ToggleTrack(pane))
+            }))
+            // samples the graph fell too far behind for, so it is not continuous
+            .push_maybe(match graph.port.dropped() {
+                0 => None,
+                dropped => Some(
+                    text(format!("Dropped: {}", dropped))
+                        .line_height(LINE_HEIGHT)
+                        .size(TEXT_SIZE),
+                ),
+            })
             .spacing(ROW_SPACING)
         ]
+        .push_maybe(
+            graph
+                .port
+                .playback()
+                .map(|playback| playback_row(&playback, pane)),
+        )
+        .push_maybe(graph.port.board().map(|board| board_row(&board, pane)))
         .spacing(ROW_SPACING),
     )
     .padding(ROW_SPACING)
     .style(style::style::graph)
 }
-fn write_buffer(data: Vec<&Graph>) -> std::io::Result<()> {
-    for graph in data {
-        let mut file = fs::File::create(".buffer")?;
-        file.write_all(graph.values.as_flattened())?;
-        file.flush()?;
-    }
-    Ok(())
+/// controls for a replayed recording, shared by every graph of the recording
+fn playback_row<'a>(playback: &Playback, pane: pane_grid::Pane) -> iced::widget::Row<'a, Message> {
+    let state = playback.state();
+    row![
+        controls_pane_button(if state.playing { "Pause" } else { "Play" }).on_press(
+            Message::Playback(
+                pane,
+                if state.playing {
+                    Command::Pause
+                } else {
+                    Command::Play
+                }
+            )
+        ),
+        controls_pane_button("Stop").on_press(Message::Playback(pane, Command::Stop)),
+        pick_list(
+            Playback::SPEEDS.map(|v| format!("{}x", v)),
+            Some(format!("{}x", state.speed)),
+            move |speed: String| Message::Playback(
+                pane,
+                Command::Speed(speed.trim_end_matches('x').parse().unwrap_or(1.0))
+            )
+        )
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH / 2.0),
+        checkbox("Loop", state.looping)
+            .on_toggle(move |looping| Message::Playback(pane, Command::Loop(looping)))
+            .text_line_height(LINE_HEIGHT)
+            .text_size(TEXT_SIZE),
+        slider(
+            0.0..=state.duration.max(f64::EPSILON),
+            state.position,
+            move |position| Message::Playback(pane, Command::Seek(position))
+        )
+        .step(state.duration.max(f64::EPSILON) / 1000.0)
+        .width(Fill),
+        text(format!("{:.1}s / {:.1}s", state.position, state.duration))
+            .line_height(LINE_HEIGHT)
+            .size(TEXT_SIZE),
+    ]
+    .height(ROW_HEIGHT)
+    .spacing(ROW_SPACING)
+    .align_y(iced::alignment::Vertical::Center)
 }
-fn get_avlb_ports() -> Vec<String> {
-    vec!["dummy".to_string()]
-        .into_iter()
-        .chain(
-            serialport::available_ports()
-                .unwrap()
-                .into_iter()
-                .map(|port| port.port_name),
+/// digital and PWM outputs of a Firmata board, shared by every graph of the board
+fn board_row<'a>(board: &Board, pane: pane_grid::Pane) -> iced::widget::Row<'a, Message> {
+    use firmata::firmata::Command;
+    let state = board.state();
+    let pin = state.selected;
+    let pwm = match state.outputs.get(&pin) {
+        Some(Command::Pwm(_, value)) => *value,
+        _ => 0,
+    };
+    // the board lists its pins after the handshake, until then there is nothing to control
+    let output = state.output_pins.iter().find(|(p, _)| *p == pin);
+    row![
+        text(match state.version {
+            Some((major, minor)) => format!("{} {}.{}", state.firmware, major, minor),
+            None => "waiting for the board".to_string(),
+        })
+        .line_height(LINE_HEIGHT)
+        .size(TEXT_SIZE)
+        .width(UNIT_WIDTH * 2.0),
+        pick_list(
+            state
+                .output_pins
+                .iter()
+                .map(|(pin, _)| format!("pin {}", pin))
+                .collect::<Vec<String>>(),
+            output.map(|(pin, _)| format!("pin {}", pin)),
+            move |pin: String| Message::Firmata(
+                pane,
+                Command::Select(pin.trim_start_matches("pin ").parse().unwrap_or(0))
+            )
         )
-        .collect()
+        .placeholder("no pins")
+        .text_line_height(LINE_HEIGHT)
+        .text_size(TEXT_SIZE)
+        .width(UNIT_WIDTH * 0.75),
+        controls_pane_button("High")
+            .on_press_maybe(output.map(|_| Message::Firmata(pane, Command::Digital(pin, true)))),
+        controls_pane_button("Low")
+            .on_press_maybe(output.map(|_| Message::Firmata(pane, Command::Digital(pin, false)))),
+    ]
+    .push_maybe(output.filter(|(_, pwm)| *pwm).map(|_| {
+        slider(0..=255, pwm, move |value| {
+            Message::Firmata(pane, Command::Pwm(pin, value))
+        })
+        .width(Fill)
+    }))
+    .push_maybe(output.filter(|(_, pwm)| *pwm).map(|_| {
+        text(format!("PWM {}", pwm))
+            .line_height(LINE_HEIGHT)
+            .size(TEXT_SIZE)
+    }))
+    .height(ROW_HEIGHT)
+    .spacing(ROW_SPACING)
+    .align_y(iced::alignment::Vertical::Center)
+}
+fn console_pane(console: &ConsolePane, pane: pane_grid::Pane) -> Container<'_, Message> {
+    let lines = console
+        .scrollback
+        .iter()
+        .map(|(sent, line)| {
+            if *sent {
+                text(format!("> {}", line))
+            } else {
+                text(line)
+            }
+        })
+        .chain(std::iter::once(text(console.partial_line())))
+        .map(|line| line.size(TEXT_SIZE).into());
+    container(
+        column![
+            scrollable(column(lines).width(Fill))
+                .anchor_bottom()
+                .height(Fill),
+            row![
+                text_input(
+                    if console.hex {
+                        "Hex bytes, e.g. 01 A0 FF"
+                    } else {
+                        "Command"
+                    },
+                    &console.input
+                )
+                .on_input(move |input| Message::ConsoleInput(pane, input))
+                .on_submit(Message::ConsoleSend(pane))
+                .line_height(LINE_HEIGHT)
+                .size(TEXT_SIZE)
+                .width(Fill),
+                pick_list(
+                    LineEnding::ALL,
+                    Some(console.line_ending),
+                    move |line_ending| Message::ConsoleLineEnding(pane, line_ending)
+                )
+                .text_line_height(LINE_HEIGHT)
+                .text_size(TEXT_SIZE)
+                .width(UNIT_WIDTH),
+                checkbox("Hex", console.hex)
+                    .on_toggle(move |hex| Message::ConsoleHex(pane, hex))
+                    .text_line_height(LINE_HEIGHT)
+                    .text_size(TEXT_SIZE),
+                controls_pane_button("Send").on_press(Message::ConsoleSend(pane)),
+                controls_pane_button("Close Pane")
+                    .on_press(Message::Close(pane, console.name.clone())),
+            ]
+            .height(ROW_HEIGHT)
+            .spacing(ROW_SPACING)
+            .align_y(iced::alignment::Vertical::Center)
+        ]
+        .spacing(ROW_SPACING),
+    )
+    .padding(ROW_SPACING)
+    .style(style::style::graph)
+}
+/// writes the graphs as one frame per sample with a value of every graph, which is what
+/// opening that many buffers reads back, graphs are lined up by their newest samples
+fn write_buffer(data: Vec<&Graph>) -> std::io::Result<()> {
+    let frames = data.iter().map(|g| g.times.len()).min().unwrap_or(0);
+    let mut file = std::io::BufWriter::new(fs::File::create(".buffer")?);
+    let mut times = std::io::BufWriter::new(fs::File::create(Recording::times_path(".buffer"))?);
+    for index in 0..frames {
+        for graph in &data {
+            let width = graph.converter.width();
+            let i = graph.times.len() - frames + index;
+            file.write_all(&graph.values[i * width..(i + 1) * width])?;
+        }
+        times.write_all(&data[0].times[data[0].times.len() - frames + index].to_le_bytes())?;
+    }
+    file.flush()?;
+    times.flush()
 }
-fn write_file(data: Vec<Vec<f32>>, path: &String) -> std::io::Result<()> {
+/// writes a time and a value column per graph, times are seconds since the unix epoch
+fn write_file(data: Vec<(Vec<f64>, Vec<f64>)>, path: &String) -> std::io::Result<()> {
     let mut f = fs::File::create(path)?;
-    let max_size = data
-        .get(data.len() - 1)
-        .ok_or(std::io::Error::other("oh no"))?
-        .len();
+    let max_size = data.last().ok_or(std::io::Error::other("oh no"))?.1.len();
     for index in 0..max_size {
         writeln!(
             f,
             "{}",
             data.iter()
-                .map(|vec| if let Some(x) = vec.get(max_size - index - 1) {
-                    x.to_string() + ","
-                } else {
-                    ",".to_string()
+                .map(|(times, values)| {
+                    let i = max_size - index - 1;
+                    match (times.get(i), values.get(i)) {
+                        (Some(t), Some(x)) => format!("{:.6},{},", t, x),
+                        _ => ",,".to_string(),
+                    }
                 })
                 .collect::<String>()
         )?
@@ -0,0 +1,266 @@
+pub mod modbus {
+    use crate::graph::graph::converter;
+    use std::time::Duration;
+    const READ_HOLDING_REGISTERS: u8 = 0x03;
+    const READ_INPUT_REGISTERS: u8 = 0x04;
+    /// one value polled from a slave, wide types take several consecutive registers
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Register {
+        pub slave: u8,
+        /// input registers are read only, holding registers are the usual ones
+        pub input: bool,
+        pub address: u16,
+        pub converter: converter,
+    }
+    impl Register {
+        pub fn count(&self) -> u16 {
+            self.converter.width().div_ceil(2) as u16
+        }
+        pub fn request(&self) -> Vec<u8> {
+            let function = match self.input {
+                true => READ_INPUT_REGISTERS,
+                false => READ_HOLDING_REGISTERS,
+            };
+            let mut request = vec![self.slave, function];
+            request.extend(self.address.to_be_bytes());
+            request.extend(self.count().to_be_bytes());
+            request.extend(crc(&request).to_le_bytes());
+            request
+        }
+        /// how long the answer is once its first `received` bytes are in, or None while that
+        /// is not known yet
+        pub fn response_len(&self, received: &[u8]) -> Option<usize> {
+            match received.get(1)? {
+                function if function & 0x80 != 0 => Some(5),
+                _ => Some(5 + 2 * self.count() as usize),
+            }
+        }
+        /// the bytes of the value, in the order the converter expects them
+        pub fn decode(&self, response: &[u8]) -> Result<Vec<u8>, String> {
+            let (body, checksum) = response.split_at(response.len().saturating_sub(2));
+            if body.len() < 3 || crc(body).to_le_bytes() != checksum {
+                return Err(format!("bad response {:02X?}", response));
+            }
+            if body[0] != self.slave || body[1] & 0x7F != self.request()[1] {
+                return Err(format!("response from the wrong request {:02X?}", response));
+            }
+            if body[1] & 0x80 != 0 {
+                return Err(format!("exception {}", exception(body[2])));
+            }
+            let data = &body[3..];
+            if body[2] as usize != data.len() || data.len() != 2 * self.count() as usize {
+                return Err(format!(
+                    "{} bytes instead of {}",
+                    data.len(),
+                    2 * self.count()
+                ));
+            }
+            // u8 and i8 sit in the low byte of their register
+            Ok(data[data.len() - self.converter.width()..].to_vec())
+        }
+    }
+    impl std::fmt::Display for Register {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}:{}{}",
+                self.slave,
+                if self.input { "i" } else { "h" },
+                self.address
+            )
+        }
+    }
+    fn exception(code: u8) -> String {
+        match code {
+            1 => "illegal function".to_string(),
+            2 => "illegal data address".to_string(),
+            3 => "illegal data value".to_string(),
+            4 => "slave device failure".to_string(),
+            6 => "slave device busy".to_string(),
+            11 => "gateway target failed to respond".to_string(),
+            code => format!("code {}", code),
+        }
+    }
+    /// CRC-16/MODBUS (poly 0xA001 reflected, init 0xFFFF), sent little endian
+    pub fn crc(data: &[u8]) -> u16 {
+        data.iter().fold(0xFFFF_u16, |mut crc, b| {
+            crc ^= *b as u16;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xA001
+                } else {
+                    crc >> 1
+                };
+            }
+            crc
+        })
+    }
+    /// parses registers typed like "1:h0 1:i10:le_u16 2:h100", as slave:kind+address with an
+    /// optional type, `h` for holding and `i` for input registers
+    pub fn parse_registers(s: &str, default: converter) -> Result<Vec<Register>, String> {
+        s.split([',', ' '])
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                let bad = || format!("bad register {:?}", token);
+                let mut parts = token.split(':');
+                let slave = parts.next().and_then(|s| s.parse().ok()).ok_or_else(bad)?;
+                let register = parts.next().ok_or_else(bad)?;
+                let input = match register.chars().next() {
+                    Some('i' | 'I') => true,
+                    Some('h' | 'H') => false,
+                    _ => return Err(bad()),
+                };
+                let address = register[1..].parse().map_err(|_| bad())?;
+                let converter = match parts.next() {
+                    Some(name) => *converter::ALL
+                        .iter()
+                        .find(|c| c.to_string() == name)
+                        .ok_or_else(bad)?,
+                    None => default,
+                };
+                if parts.next().is_some() {
+                    return Err(bad());
+                }
+                Ok(Register {
+                    slave,
+                    input,
+                    address,
+                    converter,
+                })
+            })
+            .collect()
+    }
+    /// what a Modbus port polls and how often
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct Poll {
+        pub registers: Vec<Register>,
+        pub interval: Duration,
+    }
+    impl Poll {
+        /// ms
+        pub const INTERVALS: [u64; 7] = [10, 50, 100, 250, 500, 1000, 5000];
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        fn register(converter: converter) -> Register {
+            Register {
+                slave: 1,
+                input: false,
+                address: 0,
+                converter,
+            }
+        }
+        /// the body with its CRC appended, the way a slave sends it
+        fn response(body: &[u8]) -> Vec<u8> {
+            let mut response = body.to_vec();
+            response.extend(crc(body).to_le_bytes());
+            response
+        }
+        #[test]
+        fn checksum() {
+            assert_eq!(
+                crc(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]).to_le_bytes(),
+                [0x84, 0x0A]
+            );
+            assert_eq!(crc(&[]), 0xFFFF);
+        }
+        #[test]
+        fn requests() {
+            assert_eq!(
+                register(converter::be_u16).request(),
+                vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A]
+            );
+            let wide = Register {
+                slave: 2,
+                input: true,
+                address: 0x1234,
+                converter: converter::be_f64,
+            };
+            let request = wide.request();
+            assert_eq!(request[..6], [0x02, 0x04, 0x12, 0x34, 0x00, 0x04]);
+            assert_eq!(request[6..], crc(&request[..6]).to_le_bytes());
+            assert_eq!(register(converter::u8).count(), 1);
+            assert_eq!(register(converter::le_i32).count(), 2);
+        }
+        #[test]
+        fn response_lengths() {
+            let register = register(converter::be_f32);
+            assert_eq!(register.response_len(&[]), None);
+            assert_eq!(register.response_len(&[0x01]), None);
+            assert_eq!(register.response_len(&[0x01, 0x03]), Some(9));
+            assert_eq!(register.response_len(&[0x01, 0x83]), Some(5));
+        }
+        #[test]
+        fn decoding() {
+            let value = register(converter::be_u16);
+            assert_eq!(
+                value.decode(&response(&[0x01, 0x03, 0x02, 0x12, 0x34])),
+                Ok(vec![0x12, 0x34])
+            );
+            assert_eq!(
+                register(converter::u8).decode(&response(&[0x01, 0x03, 0x02, 0x00, 0x07])),
+                Ok(vec![0x07])
+            );
+            assert_eq!(
+                register(converter::le_f32).decode(&response(&[0x01, 0x03, 0x04, 1, 2, 3, 4])),
+                Ok(vec![1, 2, 3, 4])
+            );
+            let error = |response: &[u8]| value.decode(response).unwrap_err();
+            assert_eq!(
+                error(&response(&[0x01, 0x83, 0x02])),
+                "exception illegal data address"
+            );
+            assert!(error(&response(&[0x02, 0x03, 0x02, 0x12, 0x34])).contains("wrong request"));
+            assert!(error(&response(&[0x01, 0x04, 0x02, 0x12, 0x34])).contains("wrong request"));
+            // the byte count has to match the data and the data what was asked for
+            assert!(error(&response(&[0x01, 0x03, 0x04, 0x12, 0x34])).contains("instead of"));
+            assert!(error(&response(&[0x01, 0x03, 0x04, 1, 2, 3, 4])).contains("instead of"));
+            let mut corrupted = response(&[0x01, 0x03, 0x02, 0x12, 0x34]);
+            *corrupted.last_mut().unwrap() ^= 1;
+            assert!(error(&corrupted).starts_with("bad response"));
+            assert!(error(&[0x01, 0x03]).starts_with("bad response"));
+        }
+        #[test]
+        fn parsing_registers() {
+            let registers =
+                parse_registers("1:h0 1:i10:le_u16,2:H100:be_f64", converter::be_f32).unwrap();
+            assert_eq!(
+                registers,
+                vec![
+                    register(converter::be_f32),
+                    Register {
+                        slave: 1,
+                        input: true,
+                        address: 10,
+                        converter: converter::le_u16,
+                    },
+                    Register {
+                        slave: 2,
+                        input: false,
+                        address: 100,
+                        converter: converter::be_f64,
+                    },
+                ]
+            );
+            assert_eq!(
+                registers.iter().map(Register::count).collect::<Vec<_>>(),
+                vec![2, 1, 4]
+            );
+            assert_eq!(parse_registers(" , ", converter::be_f32), Ok(vec![]));
+            for bad in [
+                "1",
+                "x:h0",
+                "300:h0",
+                "1:x0",
+                "1:h",
+                "1:h70000",
+                "1:h0:f32",
+                "1:h0:u8:1",
+                "1:hé",
+            ] {
+                assert!(parse_registers(bad, converter::u8).is_err(), "{:?}", bad);
+            }
+        }
+    }
+}
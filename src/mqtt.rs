@@ -0,0 +1,642 @@
+pub mod mqtt {
+    use crate::graph::graph::converter;
+    use crate::port::port::Stats;
+    use crate::source::source::{connect_timeout, Reconnecting, READ_TIMEOUT};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    const CONNECT: u8 = 1;
+    const CONNACK: u8 = 2;
+    const PUBLISH: u8 = 3;
+    const SUBSCRIBE: u8 = 8;
+    const SUBACK: u8 = 9;
+    const PINGREQ: u8 = 12;
+    /// `mqtt://host[:port]/filter[,filter...]`, the port defaults to 1883
+    pub fn mqtt_address(s: &str) -> Option<(String, Vec<String>)> {
+        let (host, filters) = s.strip_prefix("mqtt://")?.split_once('/')?;
+        let host = match host.contains(':') {
+            true => host.to_string(),
+            false => format!("{}:1883", host),
+        };
+        let filters: Vec<String> = filters
+            .split(',')
+            .filter(|filter| !filter.is_empty())
+            .map(String::from)
+            .collect();
+        (!filters.is_empty()).then_some((host, filters))
+    }
+    /// whether `topic` matches `filter`, `+` stands for one level and `#` for all that follow
+    pub fn matches(filter: &str, topic: &str) -> bool {
+        let mut levels = topic.split('/');
+        for part in filter.split('/') {
+            match (part, levels.next()) {
+                ("#", _) => return true,
+                ("+", Some(_)) => {}
+                (part, Some(level)) if part == level => {}
+                _ => return false,
+            }
+        }
+        levels.next().is_none()
+    }
+    pub fn is_wildcard(filter: &str) -> bool {
+        filter.contains(['+', '#'])
+    }
+    /// how the value is found in a message
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Payload {
+        /// the bytes as they are, read with the sample type
+        Raw,
+        /// text like "21.5"
+        Number,
+        /// a number in a JSON document, at a path like `sensor.values.0`
+        Json(String),
+    }
+    impl Payload {
+        pub fn converter(&self, raw: converter) -> converter {
+            match self {
+                Payload::Raw => raw,
+                Payload::Number | Payload::Json(_) => converter::be_f64,
+            }
+        }
+        /// the bytes of one sample for `converter`
+        pub fn decode(&self, payload: &[u8], raw: converter) -> Result<Vec<u8>, String> {
+            let text =
+                || std::str::from_utf8(payload).map_err(|_| "payload is not text".to_string());
+            let value = match self {
+                Payload::Raw if payload.len() < raw.width() => {
+                    return Err(format!(
+                        "{} bytes, {} needs {}",
+                        payload.len(),
+                        raw,
+                        raw.width()
+                    ))
+                }
+                Payload::Raw => return Ok(payload[..raw.width()].to_vec()),
+                Payload::Number => text()?
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("not a number: {:?}", text().unwrap_or_default()))?,
+                Payload::Json(path) => json_number(text()?, path)?,
+            };
+            Ok(value.to_be_bytes().to_vec())
+        }
+    }
+    /// `Payload` without the JSON path, for picking one
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum PayloadKind {
+        Raw,
+        Number,
+        Json,
+    }
+    impl PayloadKind {
+        pub const ALL: [PayloadKind; 3] =
+            [PayloadKind::Raw, PayloadKind::Number, PayloadKind::Json];
+    }
+    impl std::fmt::Display for PayloadKind {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    PayloadKind::Raw => "MQTT raw bytes",
+                    PayloadKind::Number => "MQTT number text",
+                    PayloadKind::Json => "MQTT JSON field",
+                }
+            )
+        }
+    }
+    #[derive(Debug, PartialEq)]
+    enum Json {
+        Null,
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Json>),
+        Object(Vec<(String, Json)>),
+    }
+    /// just enough JSON to pick numbers out of sensor messages
+    struct JsonParser<'a> {
+        text: &'a [u8],
+        position: usize,
+        /// arrays and objects the parser is inside of
+        depth: usize,
+    }
+    impl JsonParser<'_> {
+        /// deeper documents are rejected instead of overflowing the stack
+        const MAX_DEPTH: usize = 64;
+        fn skip_whitespace(&mut self) {
+            while self
+                .text
+                .get(self.position)
+                .is_some_and(|b| b.is_ascii_whitespace())
+            {
+                self.position += 1;
+            }
+        }
+        fn eat(&mut self, expected: u8) -> Option<()> {
+            self.skip_whitespace();
+            (self.text.get(self.position) == Some(&expected)).then(|| self.position += 1)
+        }
+        fn literal(&mut self, literal: &str, value: Json) -> Option<Json> {
+            let end = self.position + literal.len();
+            (self.text.get(self.position..end)? == literal.as_bytes()).then(|| {
+                self.position = end;
+                value
+            })
+        }
+        fn string(&mut self) -> Option<String> {
+            self.eat(b'"')?;
+            let mut bytes = vec![];
+            loop {
+                let b = *self.text.get(self.position)?;
+                self.position += 1;
+                match b {
+                    b'"' => return String::from_utf8(bytes).ok(),
+                    b'\\' => {
+                        let escaped = *self.text.get(self.position)?;
+                        self.position += 1;
+                        let c = match escaped {
+                            b'n' => '\n',
+                            b't' => '\t',
+                            b'r' => '\r',
+                            b'b' => '\u{8}',
+                            b'f' => '\u{c}',
+                            b'u' => {
+                                let hex = self.text.get(self.position..self.position + 4)?;
+                                self.position += 4;
+                                let code = u32::from_str_radix(std::str::from_utf8(hex).ok()?, 16);
+                                char::from_u32(code.ok()?).unwrap_or(char::REPLACEMENT_CHARACTER)
+                            }
+                            b => b as char,
+                        };
+                        bytes.extend(c.to_string().bytes());
+                    }
+                    b => bytes.push(b),
+                }
+            }
+        }
+        fn value(&mut self) -> Option<Json> {
+            self.skip_whitespace();
+            match *self.text.get(self.position)? {
+                b'n' => self.literal("null", Json::Null),
+                b't' => self.literal("true", Json::Bool(true)),
+                b'f' => self.literal("false", Json::Bool(false)),
+                b'"' => self.string().map(Json::String),
+                b'[' | b'{' if self.depth >= JsonParser::MAX_DEPTH => None,
+                b'[' => {
+                    self.position += 1;
+                    self.depth += 1;
+                    let mut items = vec![];
+                    if self.eat(b']').is_none() {
+                        loop {
+                            items.push(self.value()?);
+                            if self.eat(b']').is_some() {
+                                break;
+                            }
+                            self.eat(b',')?;
+                        }
+                    }
+                    self.depth -= 1;
+                    Some(Json::Array(items))
+                }
+                b'{' => {
+                    self.position += 1;
+                    self.depth += 1;
+                    let mut fields = vec![];
+                    if self.eat(b'}').is_none() {
+                        loop {
+                            let key = self.string()?;
+                            self.eat(b':')?;
+                            fields.push((key, self.value()?));
+                            if self.eat(b'}').is_some() {
+                                break;
+                            }
+                            self.eat(b',')?;
+                        }
+                    }
+                    self.depth -= 1;
+                    Some(Json::Object(fields))
+                }
+                _ => {
+                    let start = self.position;
+                    while self
+                        .text
+                        .get(self.position)
+                        .is_some_and(|b| b"+-.eE".contains(b) || b.is_ascii_digit())
+                    {
+                        self.position += 1;
+                    }
+                    std::str::from_utf8(&self.text[start..self.position])
+                        .ok()?
+                        .parse()
+                        .ok()
+                        .map(Json::Number)
+                }
+            }
+        }
+    }
+    /// the number at a path like `sensor.values.0`, an empty path is the whole document
+    fn json_number(text: &str, path: &str) -> Result<f64, String> {
+        let mut parser = JsonParser {
+            text: text.as_bytes(),
+            position: 0,
+            depth: 0,
+        };
+        let document = parser
+            .value()
+            .ok_or_else(|| format!("not JSON: {:?}", text))?;
+        let mut value = &document;
+        for key in path.split('.').filter(|key| !key.is_empty()) {
+            value = match value {
+                Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+                Json::Array(items) => key.parse().ok().and_then(|i: usize| items.get(i)),
+                _ => None,
+            }
+            .ok_or_else(|| format!("no {:?} in {:?}", path, text))?;
+        }
+        match value {
+            Json::Number(number) => Ok(*number),
+            Json::Bool(b) => Ok(*b as u8 as f64),
+            Json::String(s) => s
+                .trim()
+                .parse()
+                .map_err(|_| format!("{:?} in {:?} is not a number", path, text)),
+            _ => Err(format!("{:?} in {:?} is not a number", path, text)),
+        }
+    }
+    /// a message from the broker
+    #[derive(Debug, Clone)]
+    pub struct Publish {
+        pub topic: String,
+        pub payload: Vec<u8>,
+    }
+    fn string(s: &str) -> Vec<u8> {
+        let mut bytes = (s.len() as u16).to_be_bytes().to_vec();
+        bytes.extend(s.as_bytes());
+        bytes
+    }
+    fn packet(kind: u8, flags: u8, body: &[u8]) -> Vec<u8> {
+        let mut packet = vec![kind << 4 | flags];
+        let mut len = body.len();
+        loop {
+            let byte = (len % 128) as u8;
+            len /= 128;
+            match len {
+                0 => {
+                    packet.push(byte);
+                    break;
+                }
+                _ => packet.push(byte | 0x80),
+            }
+        }
+        packet.extend(body);
+        packet
+    }
+    /// MQTT 3.1.1 subscriber at QoS 0 that reconnects and subscribes again once a second while
+    /// the broker is down
+    #[derive(Debug)]
+    pub struct Client {
+        filters: Vec<String>,
+        client_id: String,
+        stream: Reconnecting<TcpStream>,
+        buffer: Vec<u8>,
+        last_sent: Instant,
+        stats: Arc<Stats>,
+    }
+    impl Client {
+        /// seconds
+        const KEEP_ALIVE: u16 = 60;
+        const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+        /// bytes in the body of one packet
+        const MAX_PACKET: usize = 1 << 20;
+        /// connects from the first `next`, which runs in the reader thread
+        pub fn new(address: &str, filters: Vec<String>, stats: Arc<Stats>) -> Self {
+            Client {
+                filters,
+                client_id: format!("serial-plotter-{}-{}", std::process::id(), stats.id),
+                stream: Reconnecting::new(address, None, stats.clone()),
+                buffer: vec![],
+                last_sent: Instant::now(),
+                stats,
+            }
+        }
+        fn connect(&mut self) -> std::io::Result<&mut TcpStream> {
+            self.stream.get(|address| {
+                let stream = Client::handshake(address, &self.filters, &self.client_id)?;
+                self.last_sent = Instant::now();
+                self.buffer.clear();
+                Ok(stream)
+            })
+        }
+        /// connects and subscribes
+        fn handshake(
+            address: &str,
+            filters: &[String],
+            client_id: &str,
+        ) -> std::io::Result<TcpStream> {
+            let mut stream = connect_timeout(address, Client::CONNECT_TIMEOUT)?;
+            stream.set_read_timeout(Some(READ_TIMEOUT))?;
+            stream.set_nodelay(true)?;
+            let mut connect = string("MQTT");
+            // protocol level 4 is 3.1.1, flags ask for a clean session
+            connect.extend([4, 0x02]);
+            connect.extend(Client::KEEP_ALIVE.to_be_bytes());
+            connect.extend(string(client_id));
+            stream.write_all(&packet(CONNECT, 0, &connect))?;
+            let started = Instant::now();
+            let mut connack = vec![];
+            while connack.len() < 4 {
+                if started.elapsed() > Client::CONNECT_TIMEOUT {
+                    return Err(std::io::ErrorKind::TimedOut.into());
+                }
+                let mut buf = [0_u8; 4];
+                match stream.read(&mut buf[..4 - connack.len()]) {
+                    Ok(0) => return Err(std::io::ErrorKind::ConnectionAborted.into()),
+                    Ok(n) => connack.extend_from_slice(&buf[..n]),
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                        ) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            if connack[0] >> 4 != CONNACK || connack[3] != 0 {
+                return Err(std::io::Error::other(format!(
+                    "broker refused the connection (code {})",
+                    connack[3]
+                )));
+            }
+            let mut subscribe = 1_u16.to_be_bytes().to_vec();
+            for filter in filters {
+                subscribe.extend(string(filter));
+                subscribe.push(0);
+            }
+            stream.write_all(&packet(SUBSCRIBE, 0x02, &subscribe))?;
+            Ok(stream)
+        }
+        /// the next whole packet in the buffer as its type, flags and body, a bad length throws
+        /// the buffer away since nothing after it can be trusted, a long one drops the connection
+        /// instead of buffering up to the 256 MB MQTT allows
+        fn packet(&mut self) -> std::io::Result<Option<(u8, u8, Vec<u8>)>> {
+            let mut len = 0;
+            for (i, byte) in self.buffer.iter().skip(1).take(4).enumerate() {
+                len += ((byte & 0x7F) as usize) << (7 * i);
+                if byte & 0x80 == 0 {
+                    if len > Client::MAX_PACKET {
+                        self.buffer.clear();
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("packet of {} bytes, more than {}", len, Client::MAX_PACKET),
+                        ));
+                    }
+                    let start = i + 2;
+                    if self.buffer.len() < start + len {
+                        return Ok(None);
+                    }
+                    let header = self.buffer[0];
+                    let body = self.buffer[start..start + len].to_vec();
+                    self.buffer.drain(..start + len);
+                    return Ok(Some((header >> 4, header & 0x0F, body)));
+                }
+            }
+            if self.buffer.len() > 5 {
+                self.buffer.clear();
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "bad packet length",
+                ));
+            }
+            Ok(None)
+        }
+        fn publish(&mut self) -> std::io::Result<Option<Publish>> {
+            while let Some((kind, flags, body)) = self.packet()? {
+                match kind {
+                    PUBLISH if body.len() >= 2 => {
+                        let topic_end = 2 + u16::from_be_bytes([body[0], body[1]]) as usize;
+                        // QoS 1 and 2 messages have a packet id after the topic
+                        let payload_start = topic_end + if flags & 0x06 != 0 { 2 } else { 0 };
+                        if body.len() < payload_start {
+                            continue;
+                        }
+                        return Ok(Some(Publish {
+                            topic: String::from_utf8_lossy(&body[2..topic_end]).to_string(),
+                            payload: body[payload_start..].to_vec(),
+                        }));
+                    }
+                    SUBACK => {
+                        for (filter, code) in self.filters.iter().zip(body.iter().skip(2)) {
+                            if *code == 0x80 {
+                                self.stats.report(format!(
+                                    "broker refused the subscription to {}",
+                                    filter
+                                ));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(None)
+        }
+        /// the next message, None if nothing came in within a read timeout
+        pub fn next(&mut self) -> std::io::Result<Option<Publish>> {
+            let result = self.publish();
+            if let Some(publish) = self.stream.check(result)? {
+                return Ok(Some(publish));
+            }
+            let ping =
+                self.last_sent.elapsed() > Duration::from_secs(Client::KEEP_ALIVE as u64 / 2);
+            let result = self.connect().and_then(|stream| match ping {
+                true => stream.write_all(&packet(PINGREQ, 0, &[])),
+                false => Ok(()),
+            });
+            if ping && result.is_ok() {
+                self.last_sent = Instant::now();
+            }
+            self.stream.check(result)?;
+            let mut buf = [0_u8; 4096];
+            let result = match self.connect()?.read(&mut buf) {
+                Ok(0) => Err(std::io::ErrorKind::ConnectionAborted.into()),
+                result => result,
+            };
+            let n = self.stream.check(result)?;
+            self.buffer.extend_from_slice(&buf[..n]);
+            let result = self.publish();
+            self.stream.check(result)
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::port::port::Stats;
+        fn client(buffer: &[u8]) -> Client {
+            let mut client = Client::new(
+                "localhost:1883",
+                vec![],
+                Arc::new(Stats::new("test", std::sync::mpsc::channel().0)),
+            );
+            client.buffer = buffer.to_vec();
+            client
+        }
+        #[test]
+        fn filters() {
+            assert!(matches("fleet/+/temp", "fleet/a/temp"));
+            assert!(!matches("fleet/+/temp", "fleet/a/b/temp"));
+            assert!(!matches("fleet/+/temp", "fleet/a"));
+            assert!(matches("fleet/#", "fleet/a/b"));
+            assert!(matches("fleet/#", "fleet"));
+            assert!(matches("#", "anything/at/all"));
+            assert!(matches("a/b", "a/b"));
+            assert!(!matches("a/b", "a/b/c"));
+            assert!(!matches("a/b/c", "a/b"));
+            assert!(is_wildcard("a/+") && is_wildcard("#") && !is_wildcard("a/b"));
+        }
+        #[test]
+        fn addresses() {
+            assert_eq!(
+                mqtt_address("mqtt://broker/a/b,c/#"),
+                Some(("broker:1883".to_string(), vec!["a/b".into(), "c/#".into()]))
+            );
+            assert_eq!(
+                mqtt_address("mqtt://broker:1884/t"),
+                Some(("broker:1884".to_string(), vec!["t".into()]))
+            );
+            assert_eq!(mqtt_address("mqtt://broker/"), None);
+            assert_eq!(mqtt_address("tcp://broker:1883"), None);
+        }
+        #[test]
+        fn packet_lengths() {
+            for len in [0, 1, 127, 128, 300, 16_383, 16_384, 70_000] {
+                let body: Vec<u8> = (0..len).map(|i| i as u8).collect();
+                let bytes = packet(PUBLISH, 0, &body);
+                let mut client = client(&bytes[..bytes.len() - 1]);
+                assert_eq!(
+                    client.packet().unwrap(),
+                    None,
+                    "incomplete packet of {}",
+                    len
+                );
+                client.buffer.push(*bytes.last().unwrap_or(&0));
+                assert_eq!(client.packet().unwrap(), Some((PUBLISH, 0, body)));
+                assert!(client.buffer.is_empty());
+            }
+            // two packets in one read
+            let mut bytes = packet(SUBACK, 0, &[0, 1, 0]);
+            bytes.extend(packet(PUBLISH, 2, &[0, 1, b't', 0, 7, 9]));
+            let mut client = client(&bytes);
+            assert_eq!(client.packet().unwrap(), Some((SUBACK, 0, vec![0, 1, 0])));
+            assert_eq!(
+                client.packet().unwrap(),
+                Some((PUBLISH, 2, vec![0, 1, b't', 0, 7, 9]))
+            );
+            assert_eq!(client.packet().unwrap(), None);
+        }
+        #[test]
+        fn bad_packet_length_clears_the_buffer() {
+            let mut client = client(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 0x00]);
+            assert_eq!(
+                client.packet().unwrap_err().kind(),
+                std::io::ErrorKind::InvalidData
+            );
+            assert!(client.buffer.is_empty());
+            assert_eq!(client.packet().unwrap(), None);
+        }
+        #[test]
+        fn long_packets_drop_the_connection() {
+            let header = |len: usize| packet(PUBLISH, 0, &vec![0; len])[..4].to_vec();
+            assert_eq!(client(&header(Client::MAX_PACKET)).packet().unwrap(), None);
+            let mut client = client(&header(Client::MAX_PACKET + 1));
+            let result = client.packet();
+            assert_eq!(
+                result.as_ref().unwrap_err().kind(),
+                std::io::ErrorKind::InvalidData
+            );
+            assert!(client.buffer.is_empty());
+            assert!(client.stream.check(result).is_err());
+            assert!(client
+                .stats
+                .disconnected
+                .load(std::sync::atomic::Ordering::Relaxed));
+        }
+        #[test]
+        fn json_paths() {
+            let text = r#"{"sensor": {"values": [1, -2.5e1, "3.5"], "ok": true}, "s": "\u00e9\""}"#;
+            assert_eq!(json_number(text, "sensor.values.0"), Ok(1.0));
+            assert_eq!(json_number(text, "sensor.values.1"), Ok(-25.0));
+            assert_eq!(json_number(text, "sensor.values.2"), Ok(3.5));
+            assert_eq!(json_number(text, "sensor.ok"), Ok(1.0));
+            assert_eq!(json_number(" 42 ", ""), Ok(42.0));
+            assert!(json_number(text, "sensor.values.3").is_err());
+            assert!(json_number(text, "sensor").is_err());
+            assert!(json_number(text, "s").is_err());
+            assert!(json_number("{\"a\": }", "a").is_err());
+            assert!(json_number("oops", "").is_err());
+        }
+        #[test]
+        fn deep_json_is_rejected() {
+            let deep = "[".repeat(100_000);
+            assert!(json_number(&deep, "").is_err());
+            let ok = format!("{}1{}", "[".repeat(64), "]".repeat(64));
+            assert_eq!(json_number(&ok, &vec!["0"; 64].join(".")), Ok(1.0));
+            let too_deep = format!("{}1{}", "[".repeat(65), "]".repeat(65));
+            assert!(json_number(&too_deep, "").is_err());
+        }
+        /// reads one packet with a one byte length, which is all the client sends
+        fn read_packet(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+            let mut header = [0_u8; 2];
+            stream.read_exact(&mut header).unwrap();
+            let mut body = vec![0_u8; header[1] as usize];
+            stream.read_exact(&mut body).unwrap();
+            (header[0] >> 4, body)
+        }
+        #[test]
+        fn scripted_broker() {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let broker = std::thread::spawn(move || {
+                for round in 0..2_u8 {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let (kind, body) = read_packet(&mut stream);
+                    assert_eq!(kind, CONNECT);
+                    assert_eq!(&body[..7], &[0, 4, b'M', b'Q', b'T', b'T', 4]);
+                    stream.write_all(&packet(CONNACK, 0, &[0, 0])).unwrap();
+                    let (kind, body) = read_packet(&mut stream);
+                    assert_eq!(kind, SUBSCRIBE);
+                    assert_eq!(&body[2..], &[0, 5, b'a', b'/', b'+', b'/', b't', 0]);
+                    stream.write_all(&packet(SUBACK, 0, &[0, 1, 0])).unwrap();
+                    let mut publish = string("a/1/t");
+                    publish.push(b'0' + round);
+                    stream.write_all(&packet(PUBLISH, 0, &publish)).unwrap();
+                }
+            });
+            let stats = Arc::new(Stats::new("test", std::sync::mpsc::channel().0));
+            let mut client = Client::new(&address, vec!["a/+/t".to_string()], stats.clone());
+            let mut received = vec![];
+            let started = Instant::now();
+            while received.len() < 2 && started.elapsed() < Duration::from_secs(10) {
+                if let Ok(Some(publish)) = client.next() {
+                    received.push(publish.payload);
+                }
+            }
+            broker.join().unwrap();
+            // the broker hung up after the first message and the client came back
+            assert_eq!(received, vec![b"0".to_vec(), b"1".to_vec()]);
+            assert!(stats.log().iter().any(|line| line.contains("reconnected")));
+        }
+        #[test]
+        fn payloads() {
+            assert_eq!(
+                Payload::Number.decode(b" 21.5\n", converter::be_f32),
+                Ok(21.5_f64.to_be_bytes().to_vec())
+            );
+            assert_eq!(
+                Payload::Raw.decode(&[1, 2, 3, 4, 5], converter::be_f32),
+                Ok(vec![1, 2, 3, 4])
+            );
+            assert!(Payload::Raw.decode(&[1, 2], converter::be_f32).is_err());
+            assert!(Payload::Number.decode(b"abc", converter::be_f32).is_err());
+        }
+    }
+}
@@ -0,0 +1,256 @@
+pub mod nmea {
+    use std::sync::{Mutex, MutexGuard};
+    /// what a GPS receiver reports, one channel each
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Quantity {
+        /// degrees, south is negative
+        Latitude,
+        /// degrees, west is negative
+        Longitude,
+        /// meters above mean sea level
+        Altitude,
+        /// km/h over ground
+        Speed,
+        /// degrees from true north
+        Course,
+        Satellites,
+        Hdop,
+    }
+    impl Quantity {
+        pub const ALL: [Quantity; 7] = [
+            Quantity::Latitude,
+            Quantity::Longitude,
+            Quantity::Altitude,
+            Quantity::Speed,
+            Quantity::Course,
+            Quantity::Satellites,
+            Quantity::Hdop,
+        ];
+    }
+    impl std::fmt::Display for Quantity {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Quantity::Latitude => "latitude",
+                    Quantity::Longitude => "longitude",
+                    Quantity::Altitude => "altitude",
+                    Quantity::Speed => "speed",
+                    Quantity::Course => "course",
+                    Quantity::Satellites => "satellites",
+                    Quantity::Hdop => "HDOP",
+                }
+            )
+        }
+    }
+    /// the values in one sentence, `kind` is the sentence type without the talker, e.g. "GGA"
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Sentence {
+        pub kind: String,
+        pub values: Vec<(Quantity, f64)>,
+    }
+    impl Sentence {
+        pub fn get(&self, quantity: Quantity) -> Option<f64> {
+            self.values
+                .iter()
+                .find(|(q, _)| *q == quantity)
+                .map(|(_, value)| *value)
+        }
+    }
+    /// `ddmm.mmmm` or `dddmm.mmmm` and its hemisphere to signed degrees
+    fn coordinate(value: &str, hemisphere: &str) -> Result<Option<f64>, String> {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        let raw: f64 = value
+            .parse()
+            .map_err(|_| format!("bad coordinate {:?}", value))?;
+        let degrees = (raw / 100.0).trunc() + (raw % 100.0) / 60.0;
+        match hemisphere {
+            "N" | "E" => Ok(Some(degrees)),
+            "S" | "W" => Ok(Some(-degrees)),
+            _ => Err(format!("bad hemisphere {:?}", hemisphere)),
+        }
+    }
+    fn number(value: &str) -> Result<Option<f64>, String> {
+        match value {
+            "" => Ok(None),
+            value => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("bad number {:?}", value)),
+        }
+    }
+    /// checks a sentence like `$GPGGA,...*47` and picks out the values of GGA, RMC and VTG,
+    /// other sentences come back without values and empty fields (no fix yet) are left out
+    pub fn parse(line: &str) -> Result<Sentence, String> {
+        let line = line.trim();
+        let body = line
+            .strip_prefix('$')
+            .ok_or_else(|| format!("not a sentence {:?}", line))?;
+        let (body, checksum) = body
+            .rsplit_once('*')
+            .ok_or_else(|| format!("no checksum in {:?}", line))?;
+        let expected =
+            u8::from_str_radix(checksum, 16).map_err(|_| format!("bad checksum in {:?}", line))?;
+        if body.bytes().fold(0, |acc, b| acc ^ b) != expected {
+            return Err(format!("checksum mismatch in {:?}", line));
+        }
+        let fields: Vec<&str> = body.split(',').collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or("");
+        let kind = match field(0) {
+            address if address.len() == 5 && address.is_ascii() => address[2..].to_string(),
+            _ => String::new(),
+        };
+        let mut values = vec![];
+        let mut add = |quantity: Quantity, value: Option<f64>| {
+            if let Some(value) = value {
+                values.push((quantity, value));
+            }
+        };
+        match kind.as_str() {
+            "GGA" => {
+                if !matches!(field(6), "" | "0") {
+                    add(Quantity::Latitude, coordinate(field(2), field(3))?);
+                    add(Quantity::Longitude, coordinate(field(4), field(5))?);
+                    add(Quantity::Altitude, number(field(9))?);
+                }
+                add(Quantity::Satellites, number(field(7))?);
+                add(Quantity::Hdop, number(field(8))?);
+            }
+            // status V means the receiver has no fix
+            "RMC" if field(2) == "A" => {
+                add(Quantity::Latitude, coordinate(field(3), field(4))?);
+                add(Quantity::Longitude, coordinate(field(5), field(6))?);
+                add(
+                    Quantity::Speed,
+                    number(field(7))?.map(|knots| knots * 1.852),
+                );
+                add(Quantity::Course, number(field(8))?);
+            }
+            "VTG" => {
+                add(Quantity::Course, number(field(1))?);
+                add(Quantity::Speed, number(field(7))?);
+            }
+            _ => {}
+        }
+        Ok(Sentence { kind, values })
+    }
+    /// positions of a GPS receiver as (latitude, longitude), shared with the track view
+    #[derive(Debug, Default)]
+    pub struct Track {
+        points: Mutex<Vec<(f64, f64)>>,
+    }
+    impl Track {
+        /// a day of fixes at 1 Hz, after that every other point is dropped so the track keeps
+        /// its whole extent
+        const MAX_POINTS: usize = 86_400;
+        pub fn push(&self, latitude: f64, longitude: f64) {
+            let mut points = self.points();
+            if points.len() >= Track::MAX_POINTS {
+                let mut i = 0;
+                points.retain(|_| {
+                    i += 1;
+                    i % 2 == 0
+                });
+            }
+            points.push((latitude, longitude));
+        }
+        /// held while drawing rather than copied, the receiver waits for it
+        pub fn points(&self) -> MutexGuard<'_, Vec<(f64, f64)>> {
+            self.points.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        fn close(a: Option<f64>, b: f64) -> bool {
+            a.is_some_and(|a| (a - b).abs() < 1e-6)
+        }
+        #[test]
+        fn fixes() {
+            let gga =
+                parse("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n")
+                    .unwrap();
+            assert_eq!(gga.kind, "GGA");
+            assert!(close(gga.get(Quantity::Latitude), 48.0 + 7.038 / 60.0));
+            assert!(close(gga.get(Quantity::Longitude), 11.0 + 31.0 / 60.0));
+            assert_eq!(gga.get(Quantity::Altitude), Some(545.4));
+            assert_eq!(gga.get(Quantity::Satellites), Some(8.0));
+            assert_eq!(gga.get(Quantity::Hdop), Some(0.9));
+            let rmc = parse("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A")
+                .unwrap();
+            assert_eq!(rmc.kind, "RMC");
+            assert!(close(rmc.get(Quantity::Speed), 22.4 * 1.852));
+            assert_eq!(rmc.get(Quantity::Course), Some(84.4));
+            let vtg = parse("$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*48").unwrap();
+            assert_eq!(
+                vtg.values,
+                vec![(Quantity::Course, 54.7), (Quantity::Speed, 10.2)]
+            );
+        }
+        #[test]
+        fn southern_and_western_hemispheres() {
+            let gga = parse("$GNGGA,092725.00,3354.9282,S,15112.4371,W,2,11,,,M,,M,,*6C").unwrap();
+            assert!(close(gga.get(Quantity::Latitude), -(33.0 + 54.9282 / 60.0)));
+            assert!(close(
+                gga.get(Quantity::Longitude),
+                -(151.0 + 12.4371 / 60.0)
+            ));
+            // the empty HDOP and altitude fields are left out
+            assert_eq!(gga.get(Quantity::Hdop), None);
+            assert_eq!(gga.get(Quantity::Altitude), None);
+            assert_eq!(gga.get(Quantity::Satellites), Some(11.0));
+        }
+        #[test]
+        fn no_fix() {
+            let gga = parse("$GPGGA,,,,,,0,00,99.99,,,,,,*48").unwrap();
+            assert_eq!(
+                gga.values,
+                vec![(Quantity::Satellites, 0.0), (Quantity::Hdop, 99.99)]
+            );
+            let rmc = parse("$GPRMC,,V,,,,,,,,,,N*53").unwrap();
+            assert_eq!(rmc.kind, "RMC");
+            assert!(rmc.values.is_empty());
+            assert!(parse("$GPVTG,,,,,,,,,N*30").unwrap().values.is_empty());
+            let gsv = parse("$GPGSV,1,1,00*79").unwrap();
+            assert_eq!(gsv.kind, "GSV");
+            assert!(gsv.values.is_empty());
+        }
+        #[test]
+        fn bad_sentences() {
+            // one digit of the latitude changed
+            assert!(
+                parse("$GPGGA,123519,4807.039,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47")
+                    .unwrap_err()
+                    .contains("checksum mismatch")
+            );
+            assert!(parse("$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*49").is_err());
+            assert!(parse("$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K").is_err());
+            assert!(parse("$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*ZZ").is_err());
+            assert!(parse("GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*48").is_err());
+            assert!(parse("").is_err());
+            // fields that check out but do not parse
+            assert!(
+                parse("$GPGGA,123519,4807.038,X,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*51")
+                    .unwrap_err()
+                    .contains("hemisphere")
+            );
+            assert!(parse("$GPVTG,054.7,T,034.4,M,005.5,N,1O.2,K*07")
+                .unwrap_err()
+                .contains("bad number"));
+        }
+        #[test]
+        fn track_is_bounded() {
+            let track = Track::default();
+            for i in 0..Track::MAX_POINTS + 10 {
+                track.push(i as f64, 0.0);
+            }
+            let points = track.points();
+            assert!(points.len() <= Track::MAX_POINTS);
+            assert_eq!(points.first(), Some(&(1.0, 0.0)));
+            assert_eq!(points.last(), Some(&((Track::MAX_POINTS + 9) as f64, 0.0)));
+        }
+    }
+}
@@ -1,256 +1,1769 @@
 pub mod port {
+    use crate::console::console::{Console, ConsoleSource};
+    use crate::firmata::firmata::{self, Board, Decoder, Message, Pin};
+    use crate::frame::frame::{Framer, Framing};
+    use crate::generator::generator::{Generator, GeneratorSource};
+    use crate::graph::graph::converter;
+    use crate::layout::layout::Field;
+    use crate::modbus::modbus::{Poll, Register};
+    use crate::mqtt::mqtt::{self, Client, Payload};
+    use crate::nmea::nmea::{self, Quantity, Track};
+    use crate::replay::replay::{Playback, Recording, ReplaySource};
+    use crate::source::source::{
+        tcp_address, udp_address, SerialSource, Source, StreamSource, TcpSource, UdpSource,
+        READ_TIMEOUT,
+    };
     use serialport;
+    use std::collections::VecDeque;
     use std::fmt::Debug;
-    use std::sync::mpsc;
-    use std::time::Duration;
-    type Item = [u8; 4];
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc, Condvar, Mutex, OnceLock};
+    use std::time::{Duration, Instant, SystemTime};
+    /// one value of a channel and when its bytes were read, relative to when the port was opened
+    #[derive(Debug, Clone)]
+    pub struct Sample {
+        pub time: Duration,
+        pub bytes: Vec<u8>,
+    }
+    type Item = Sample;
     #[allow(dead_code)]
     pub trait Port: Debug + Iterator<Item = Item> {
         fn name(&self) -> String {
             "dummy".to_string()
         }
+        fn settings(&self) -> Option<PortSettings> {
+            None
+        }
+        fn status(&self) -> String {
+            String::new()
+        }
+        fn converter(&self) -> converter {
+            converter::be_f32
+        }
+        fn errors(&self) -> Vec<String> {
+            vec![]
+        }
+        fn console(&self) -> Option<Arc<Console>> {
+            None
+        }
+        /// wall clock time the sample times are counted from
+        fn started(&self) -> Option<SystemTime> {
+            None
+        }
+        /// controls of a replayed recording
+        fn playback(&self) -> Option<Arc<Playback>> {
+            None
+        }
+        /// the Firmata board this channel is a pin of
+        fn board(&self) -> Option<Arc<Board>> {
+            None
+        }
+        /// positions of the GPS receiver this channel is read from
+        fn track(&self) -> Option<Arc<Track>> {
+            None
+        }
+        /// id of the physical port (see `PortHandle`) this channel is read from
+        fn physical_port(&self) -> Option<usize> {
+            None
+        }
+        /// another reader of the same channel that gets every sample from now on
+        fn subscribe(&self) -> Option<Box<dyn Port>> {
+            None
+        }
+        /// samples this reader lost because it fell too far behind, see `Buffering`
+        fn dropped(&self) -> usize {
+            0
+        }
+    }
+    /// something that went wrong in a reader thread, sent back to the app
+    #[derive(Debug, Clone)]
+    pub struct PortError {
+        pub port: String,
+        pub message: String,
+    }
+    /// counters and error log shared between a reader thread and all of its splits
+    #[derive(Debug)]
+    pub struct Stats {
+        pub id: usize,
+        name: String,
+        opened: Instant,
+        started: SystemTime,
+        errors: mpsc::Sender<PortError>,
+        log: Mutex<Vec<(Duration, String, usize)>>,
+        pub disconnected: AtomicBool,
+        pub rejected_frames: AtomicUsize,
+        pub malformed_lines: AtomicUsize,
+        pub malformed_packets: AtomicUsize,
+        pub malformed_sentences: AtomicUsize,
+        pub lost_packets: AtomicUsize,
+        pub out_of_order_packets: AtomicUsize,
+        /// whether the datagrams carry sequence numbers, unset for sources that are no datagrams
+        pub sequenced: OnceLock<bool>,
+        /// set to ask the reader thread to finish
+        pub stop: AtomicBool,
+    }
+    impl Stats {
+        const LOG_LENGTH: usize = 100;
+        pub fn new(name: &str, errors: mpsc::Sender<PortError>) -> Self {
+            static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+            Stats {
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+                name: name.to_string(),
+                opened: Instant::now(),
+                started: SystemTime::now(),
+                errors,
+                log: Mutex::new(vec![]),
+                disconnected: AtomicBool::new(false),
+                rejected_frames: AtomicUsize::new(0),
+                malformed_lines: AtomicUsize::new(0),
+                malformed_packets: AtomicUsize::new(0),
+                malformed_sentences: AtomicUsize::new(0),
+                lost_packets: AtomicUsize::new(0),
+                out_of_order_packets: AtomicUsize::new(0),
+                sequenced: OnceLock::new(),
+                stop: AtomicBool::new(false),
+            }
+        }
+        /// logs `message`, repeats of the last message only bump its count and are not sent to
+        /// the app again
+        pub fn report(&self, message: impl Into<String>) {
+            let message = message.into();
+            let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+            match log.last_mut() {
+                Some((_, last, count)) if *last == message => *count += 1,
+                _ => {
+                    log.push((self.opened.elapsed(), message.clone(), 1));
+                    if log.len() > Stats::LOG_LENGTH {
+                        log.remove(0);
+                    }
+                    let _ = self.errors.send(PortError {
+                        port: self.name.clone(),
+                        message,
+                    });
+                }
+            }
+        }
+        /// reports anything that is more than "nothing to read yet"
+        pub fn check<T>(&self, result: std::io::Result<T>) -> Option<T> {
+            match result {
+                Ok(v) => Some(v),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock
+                            | std::io::ErrorKind::TimedOut
+                            | std::io::ErrorKind::Interrupted
+                            | std::io::ErrorKind::NotConnected
+                    ) =>
+                {
+                    None
+                }
+                Err(e) => {
+                    self.report(e.to_string());
+                    None
+                }
+            }
+        }
+        pub fn log(&self) -> Vec<String> {
+            self.log
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|(time, message, count)| match count {
+                    1 => format!("[{:.1}s] {}", time.as_secs_f32(), message),
+                    count => format!("[{:.1}s] {} (x{})", time.as_secs_f32(), message, count),
+                })
+                .collect()
+        }
+    }
+    impl std::fmt::Display for Stats {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            if self.disconnected.load(Ordering::Relaxed) {
+                write!(f, "DISCONNECTED ")?;
+            }
+            let mut counts: Vec<String> = [
+                ("rejected frames", &self.rejected_frames),
+                ("malformed lines", &self.malformed_lines),
+                ("malformed packets", &self.malformed_packets),
+                ("malformed sentences", &self.malformed_sentences),
+            ]
+            .into_iter()
+            .filter_map(|(name, count)| match count.load(Ordering::Relaxed) {
+                0 => None,
+                v => Some(format!("{}: {}", name, v)),
+            })
+            .collect();
+            // without sequence numbers a zero would claim that nothing was lost
+            for (name, count) in [
+                ("lost packets", &self.lost_packets),
+                ("out of order packets", &self.out_of_order_packets),
+            ] {
+                match self.sequenced.get() {
+                    Some(true) => {
+                        counts.push(format!("{}: {}", name, count.load(Ordering::Relaxed)))
+                    }
+                    Some(false) => counts.push(format!("{}: not tracked", name)),
+                    None => {}
+                }
+            }
+            write!(f, "{}", counts.join(", "))
+        }
+    }
+    /// channels a reader thread found after the port was opened, e.g. new MQTT topics
+    type Arrivals = Arc<Mutex<Vec<Box<dyn Port + Send>>>>;
+    /// owns the reader thread of one physical port, closing or dropping it lets the thread
+    /// finish and release the device
+    #[derive(Debug)]
+    pub struct PortHandle {
+        pub name: String,
+        pub settings: Option<PortSettings>,
+        stats: Arc<Stats>,
+        arrivals: Arrivals,
+        thread: Option<std::thread::JoinHandle<()>>,
+    }
+    impl PortHandle {
+        /// channels that showed up since the last call
+        pub fn take_channels(&self) -> Vec<Box<dyn Port>> {
+            std::mem::take(&mut *self.arrivals.lock().unwrap_or_else(|e| e.into_inner()))
+                .into_iter()
+                .map(|port| port as Box<dyn Port>)
+                .collect()
+        }
+        pub fn id(&self) -> usize {
+            self.stats.id
+        }
+        pub fn status(&self) -> String {
+            self.stats.to_string()
+        }
+        /// false once the thread stopped, e.g. because every channel was closed
+        pub fn running(&self) -> bool {
+            self.thread
+                .as_ref()
+                .is_some_and(|thread| !thread.is_finished())
+        }
+        /// asks the reader thread to stop without waiting for it, it lets go of the device once
+        /// its current read, reconnect attempt or Modbus poll is over, so keep the handle and
+        /// pass it to `from_string` when the port is opened again
+        pub fn close(&self) {
+            self.stats.stop.store(true, Ordering::Relaxed);
+        }
+    }
+    impl Drop for PortHandle {
+        fn drop(&mut self) {
+            self.close()
+        }
+    }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Protocol {
+        Binary,
+        Text,
+        Firmata,
+        Modbus,
+        Nmea,
+    }
+    impl Protocol {
+        pub const ALL: [Protocol; 5] = [
+            Protocol::Binary,
+            Protocol::Text,
+            Protocol::Firmata,
+            Protocol::Modbus,
+            Protocol::Nmea,
+        ];
+    }
+    impl std::fmt::Display for Protocol {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Protocol::Binary => "binary words",
+                    Protocol::Text => "text lines",
+                    Protocol::Firmata => "Firmata",
+                    Protocol::Modbus => "Modbus RTU",
+                    Protocol::Nmea => "NMEA 0183",
+                }
+            )
+        }
+    }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct PortSettings {
+        pub baud_rate: u32,
+        pub data_bits: serialport::DataBits,
+        pub parity: serialport::Parity,
+        pub stop_bits: serialport::StopBits,
+        pub flow_control: serialport::FlowControl,
+        pub timeout: Duration,
+    }
+    impl PortSettings {
+        pub const BAUD_RATES: [u32; 14] = [
+            300, 1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200, 230400, 250000, 460800,
+            921600, 1000000,
+        ];
+        /// how long a read waits for data, in ms
+        pub const TIMEOUTS: [u64; 4] = [10, 100, 500, 1000];
+        pub fn builder(&self, path: &str) -> serialport::SerialPortBuilder {
+            serialport::new(path, self.baud_rate)
+                .data_bits(self.data_bits)
+                .parity(self.parity)
+                .stop_bits(self.stop_bits)
+                .flow_control(self.flow_control)
+                .timeout(self.timeout)
+        }
+    }
+    impl Default for PortSettings {
+        fn default() -> Self {
+            PortSettings {
+                baud_rate: 9600,
+                data_bits: serialport::DataBits::Eight,
+                parity: serialport::Parity::None,
+                stop_bits: serialport::StopBits::One,
+                flow_control: serialport::FlowControl::None,
+                timeout: Duration::from_millis(100),
+            }
+        }
+    }
+    impl std::fmt::Display for PortSettings {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{} {}{}{}",
+                self.baud_rate,
+                u8::from(self.data_bits),
+                match self.parity {
+                    serialport::Parity::None => "N",
+                    serialport::Parity::Odd => "O",
+                    serialport::Parity::Even => "E",
+                },
+                u8::from(self.stop_bits)
+            )?;
+            match self.flow_control {
+                serialport::FlowControl::None => Ok(()),
+                serialport::FlowControl::Software => write!(f, " XON/XOFF"),
+                serialport::FlowControl::Hardware => write!(f, " RTS/CTS"),
+            }
+        }
+    }
+    /// what a channel does with a new sample while a reader still has `capacity` waiting
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Overflow {
+        DropOldest,
+        DropNewest,
+        /// the reader thread waits, stalling every channel of the port
+        Block,
+    }
+    impl Overflow {
+        pub const ALL: [Overflow; 3] =
+            [Overflow::DropOldest, Overflow::DropNewest, Overflow::Block];
+    }
+    impl std::fmt::Display for Overflow {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Overflow::DropOldest => "drop oldest",
+                    Overflow::DropNewest => "drop newest",
+                    Overflow::Block => "block reader",
+                }
+            )
+        }
+    }
+    /// how many samples a reader of a channel may fall behind, and what happens then
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Buffering {
+        pub capacity: usize,
+        pub overflow: Overflow,
+    }
+    impl Buffering {
+        pub const CAPACITIES: [usize; 4] = [1_000, 10_000, 100_000, 1_000_000];
+    }
+    impl Default for Buffering {
+        fn default() -> Self {
+            Buffering {
+                capacity: 100_000,
+                overflow: Overflow::DropOldest,
+            }
+        }
+    }
+    /// samples waiting for one subscriber
+    #[derive(Debug, Default)]
+    struct Queue {
+        samples: Mutex<VecDeque<Item>>,
+        /// signalled whenever samples are taken or the subscriber goes away
+        taken: Condvar,
+        closed: AtomicBool,
+        /// set once a graph starts taking samples, channels still waiting in the open ports
+        /// list never block the reader
+        consumed: AtomicBool,
+        dropped: AtomicUsize,
+    }
+    impl Queue {
+        fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Item>> {
+            self.samples.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+    /// the receiving end of one channel, dropping it unsubscribes
+    #[derive(Debug)]
+    struct Receiver {
+        queue: Arc<Queue>,
+    }
+    impl Receiver {
+        fn try_recv(&self) -> Option<Item> {
+            self.queue.consumed.store(true, Ordering::Relaxed);
+            let sample = self.queue.lock().pop_front();
+            self.queue.taken.notify_all();
+            sample
+        }
+        fn dropped(&self) -> usize {
+            self.queue.dropped.load(Ordering::Relaxed)
+        }
+    }
+    impl Drop for Receiver {
+        fn drop(&mut self) {
+            self.queue.closed.store(true, Ordering::Relaxed);
+            self.queue.taken.notify_all();
+        }
+    }
+    /// the sending end of one channel, every subscriber gets its own copy of each sample
+    #[derive(Debug)]
+    struct Broadcast {
+        subscribers: Mutex<Vec<Arc<Queue>>>,
+        buffering: Buffering,
+        /// a blocked send gives up once the port is closed
+        stats: Arc<Stats>,
+        /// Modbus polls of this register that timed out or got an exception
+        failed_polls: AtomicUsize,
+    }
+    impl Broadcast {
+        fn new(buffering: Buffering, stats: Arc<Stats>) -> Self {
+            Broadcast {
+                failed_polls: AtomicUsize::new(0),
+                subscribers: Mutex::new(vec![]),
+                buffering,
+                stats,
+            }
+        }
+        fn subscribe(&self) -> Receiver {
+            let queue = Arc::new(Queue::default());
+            self.subscribers
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(queue.clone());
+            Receiver { queue }
+        }
+        /// false once every subscriber is gone
+        fn send(&self, sample: Item) -> bool {
+            // not locked while blocking, the app may subscribe in the meantime
+            let subscribers = {
+                let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+                subscribers.retain(|queue| !queue.closed.load(Ordering::Relaxed));
+                subscribers.clone()
+            };
+            for queue in &subscribers {
+                self.push(queue, sample.clone());
+            }
+            !subscribers.is_empty()
+        }
+        fn push(&self, queue: &Queue, sample: Item) {
+            let overflow = match self.buffering.overflow {
+                Overflow::Block if !queue.consumed.load(Ordering::Relaxed) => Overflow::DropOldest,
+                overflow => overflow,
+            };
+            let mut samples = queue.lock();
+            while samples.len() >= self.buffering.capacity {
+                match overflow {
+                    Overflow::DropOldest => {
+                        samples.pop_front();
+                        queue.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Overflow::DropNewest => {
+                        queue.dropped.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    }
+                    Overflow::Block => {
+                        if queue.closed.load(Ordering::Relaxed)
+                            || self.stats.stop.load(Ordering::Relaxed)
+                        {
+                            return;
+                        }
+                        samples = queue
+                            .taken
+                            .wait_timeout(samples, READ_TIMEOUT)
+                            .unwrap_or_else(|e| e.into_inner())
+                            .0;
+                    }
+                }
+            }
+            samples.push_back(sample);
+        }
+        fn listening(&self) -> bool {
+            self.subscribers
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .any(|queue| !queue.closed.load(Ordering::Relaxed))
+        }
     }
     #[derive(Debug)]
     struct MultiPort {
-        port: mpsc::Receiver<Item>,
+        port: Receiver,
+        channel: Arc<Broadcast>,
         name: String,
+        settings: Option<PortSettings>,
+        stats: Arc<Stats>,
+        /// none for sources that cannot be written to
+        console: Option<Arc<Console>>,
+        playback: Option<Arc<Playback>>,
+        board: Option<Arc<Board>>,
+        track: Option<Arc<Track>>,
+        converter: converter,
     }
     impl Iterator for MultiPort {
         type Item = Item;
         fn next(&mut self) -> Option<Self::Item> {
-            self.port.try_recv().ok()
+            self.port.try_recv()
         }
     }
     impl Port for MultiPort {
         fn name(&self) -> String {
             self.name.clone()
         }
+        fn settings(&self) -> Option<PortSettings> {
+            self.settings
+        }
+        fn status(&self) -> String {
+            match self.channel.failed_polls.load(Ordering::Relaxed) {
+                0 => self.stats.to_string(),
+                failed => [self.stats.to_string(), format!("failed polls: {}", failed)]
+                    .into_iter()
+                    .filter(|status| !status.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }
+        }
+        fn converter(&self) -> converter {
+            self.converter
+        }
+        fn errors(&self) -> Vec<String> {
+            self.stats.log()
+        }
+        fn console(&self) -> Option<Arc<Console>> {
+            self.console.clone()
+        }
+        fn started(&self) -> Option<SystemTime> {
+            Some(self.stats.started)
+        }
+        fn playback(&self) -> Option<Arc<Playback>> {
+            self.playback.clone()
+        }
+        fn board(&self) -> Option<Arc<Board>> {
+            self.board.clone()
+        }
+        fn track(&self) -> Option<Arc<Track>> {
+            self.track.clone()
+        }
+        fn physical_port(&self) -> Option<usize> {
+            Some(self.stats.id)
+        }
+        fn subscribe(&self) -> Option<Box<dyn Port>> {
+            Some(Box::new(MultiPort {
+                port: self.channel.subscribe(),
+                channel: self.channel.clone(),
+                name: self.name.clone(),
+                settings: self.settings,
+                stats: self.stats.clone(),
+                console: self.console.clone(),
+                playback: self.playback.clone(),
+                board: self.board.clone(),
+                track: self.track.clone(),
+                converter: self.converter,
+            }))
+        }
+        fn dropped(&self) -> usize {
+            self.port.dropped()
+        }
+    }
+    /// what a reader thread does with the device of one protocol
+    trait Reader: Send + Sized + 'static {
+        fn stats(&self) -> &Stats;
+        /// before the first step, e.g. to find out which channels there are
+        fn start(&mut self) {}
+        /// reads what the device sent and passes it on, false once every channel was closed
+        fn step(&mut self) -> bool;
+        /// steps on a thread of its own until the port is closed or nobody listens any more
+        fn spawn(mut self) -> std::thread::JoinHandle<()> {
+            std::thread::spawn(move || {
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    self.start();
+                    loop {
+                        if self.stats().stop.load(Ordering::Relaxed) || !self.step() {
+                            return;
+                        }
+                    }
+                }));
+                if let Err(panic) = result {
+                    report_panic(self.stats(), panic);
+                }
+            })
+        }
     }
     #[derive(Debug)]
     struct PhysicalPort {
-        port: Box<dyn serialport::SerialPort>,
+        port: Box<dyn Source>,
         name: String,
-        values: Vec<(mpsc::Sender<Item>, Option<mpsc::Receiver<Item>>)>,
-        internal_ports: usize,
+        settings: Option<PortSettings>,
+        /// bytes read but not yet split into frames
+        framer: Framer,
+        stats: Arc<Stats>,
+        console: Option<Arc<Console>>,
+        playback: Option<Arc<Playback>>,
+        values: Vec<Arc<Broadcast>>,
+        layout: Vec<Field>,
         current_port_read: usize,
+        /// `READ_SIZE` bytes that every read of the port lands in
+        buffer: Vec<u8>,
     }
     impl PhysicalPort {
-        fn new(port: Box<dyn serialport::SerialPort>, internal_ports: usize, name: String) -> Self {
-            let mut values = Vec::with_capacity(internal_ports);
-            for _ in 0..internal_ports {
-                let (sender, receiver) = mpsc::channel::<Item>();
-                values.push((sender, Some(receiver)));
-            }
+        const READ_SIZE: usize = 1 << 16;
+        #[allow(clippy::too_many_arguments)]
+        fn new(
+            port: Box<dyn Source>,
+            layout: Vec<Field>,
+            name: String,
+            settings: Option<PortSettings>,
+            framing: Framing,
+            stats: Arc<Stats>,
+            console: Option<Arc<Console>>,
+            playback: Option<Arc<Playback>>,
+            buffering: Buffering,
+        ) -> Self {
+            let values = layout
+                .iter()
+                .filter(|f| matches!(f, Field::Value { .. }))
+                .map(|_| Arc::new(Broadcast::new(buffering, stats.clone())))
+                .collect();
             PhysicalPort {
                 port,
                 name,
+                settings,
+                framer: Framer::new(framing),
+                stats,
+                console,
+                playback,
                 values,
-                internal_ports,
+                layout,
                 current_port_read: 0,
+                buffer: vec![0; PhysicalPort::READ_SIZE],
             }
         }
         fn split(&mut self) -> Option<Box<dyn Port>> {
             self.current_port_read += 1;
+            let (name, converter) = self
+                .layout
+                .iter()
+                .filter_map(|f| match f {
+                    Field::Value { name, converter } => Some((name, converter)),
+                    Field::Padding(_) => None,
+                })
+                .nth(self.current_port_read - 1)?;
             Some(Box::new(MultiPort {
-                port: self.values.get_mut(self.current_port_read - 1)?.1.take()?,
-                name: format!("{} split {}", self.name.clone(), self.current_port_read),
+                port: self.values.get(self.current_port_read - 1)?.subscribe(),
+                channel: self.values.get(self.current_port_read - 1)?.clone(),
+                name: match name {
+                    Some(name) => format!("{} {}", self.name, name),
+                    None => format!("{} split {}", self.name.clone(), self.current_port_read),
+                },
+                settings: self.settings,
+                stats: self.stats.clone(),
+                console: self.console.clone(),
+                playback: self.playback.clone(),
+                board: None,
+                track: None,
+                converter: *converter,
             }))
         }
-        fn step_at(mut self) -> Option<String> {
-            std::thread::spawn(move || loop {
-                if !self.next() {
-                    return Some("port closed");
+        fn frame_len(&self) -> usize {
+            self.layout.iter().map(|f| f.width()).sum()
+        }
+        /// false once every channel has been closed
+        fn send_frame(&self, frame: &[u8], time: Duration) -> bool {
+            let mut offset = 0;
+            let mut senders = self.values.iter();
+            let mut listening = false;
+            for field in &self.layout {
+                if let Field::Value { .. } = field {
+                    let Some(channel) = senders.next() else {
+                        return false;
+                    };
+                    let sample = Sample {
+                        time,
+                        bytes: frame[offset..offset + field.width()].to_vec(),
+                    };
+                    listening |= channel.send(sample);
                 }
-                std::thread::sleep(
-                    Duration::from_secs(1) / self.port.baud_rate().expect("no baud rate"),
+                offset += field.width();
+            }
+            listening
+        }
+    }
+    impl Reader for PhysicalPort {
+        fn stats(&self) -> &Stats {
+            &self.stats
+        }
+        fn step(&mut self) -> bool {
+            let frame_len = self.frame_len();
+            if self.port.datagrams() {
+                if let Some(n @ 1..) = self.stats.check(self.port.read(&mut self.buffer)) {
+                    if n != frame_len {
+                        self.stats.rejected_frames.fetch_add(1, Ordering::Relaxed);
+                        self.stats
+                            .report(format!("datagram of {} bytes, expected {}", n, frame_len));
+                    } else {
+                        let time = self.port.recorded_at();
+                        return self.send_frame(
+                            &self.buffer[..n],
+                            time.unwrap_or_else(|| self.stats.opened.elapsed()),
+                        );
+                    }
+                }
+                return true;
+            }
+            let Some(n @ 1..) = self.stats.check(self.port.read(&mut self.buffer)) else {
+                return true;
+            };
+            let time = self.stats.opened.elapsed();
+            self.framer.push(&self.buffer[..n]);
+            while let Some(frame) = self.framer.next_frame(frame_len) {
+                if !self.send_frame(&frame, time) {
+                    return false;
+                }
+            }
+            if self.framer.rejected > 0 {
+                self.stats
+                    .rejected_frames
+                    .fetch_add(std::mem::take(&mut self.framer.rejected), Ordering::Relaxed);
+                self.stats
+                    .report("rejected frame with a bad length or checksum, resyncing");
+            }
+            if self.framer.malformed > 0 {
+                self.stats.malformed_packets.fetch_add(
+                    std::mem::take(&mut self.framer.malformed),
+                    Ordering::Relaxed,
                 );
-            });
+                self.stats.report(format!(
+                    "malformed packet, expected {} decoded bytes",
+                    frame_len
+                ));
+            }
+            true
+        }
+    }
+    /// reads newline terminated text in the Arduino Serial Plotter format, e.g. `1.0,2.5` or
+    /// `temp:21.5 humidity:40`, with one channel per column
+    #[derive(Debug)]
+    struct LinePort {
+        port: Box<dyn Source>,
+        name: String,
+        settings: Option<PortSettings>,
+        lines: Lines,
+        columns: Vec<(String, Arc<Broadcast>)>,
+        /// the columns of the frame layout, for when the first lines do not tell
+        labels: Vec<Option<String>>,
+        buffering: Buffering,
+        arrivals: Arrivals,
+        stats: Arc<Stats>,
+        console: Option<Arc<Console>>,
+        playback: Option<Arc<Playback>>,
+    }
+    impl LinePort {
+        const DISCOVERY: Duration = Duration::from_secs(2);
+        fn read_lines(&mut self) -> Vec<String> {
+            self.lines
+                .read(&mut self.port, &self.stats.malformed_lines, &self.stats)
+        }
+        fn parse(line: &str) -> Option<Vec<(Option<String>, f64)>> {
+            let mut values = vec![];
+            let mut label = None;
+            for token in line
+                .split([',', ' ', '\t'])
+                .filter(|token| !token.is_empty())
+            {
+                match token.split_once(':') {
+                    Some((name, "")) => label = Some(name.to_string()),
+                    Some((name, value)) => {
+                        values.push((Some(name.to_string()), value.parse().ok()?))
+                    }
+                    None => values.push((label.take(), token.parse().ok()?)),
+                }
+            }
+            Some(values)
+        }
+        /// waits for the first whole line to find out how many columns there are and what they
+        /// are called
+        fn discover(&mut self, timeout: Duration) -> Option<Vec<Option<String>>> {
+            let start = Instant::now();
+            let mut first = true;
+            while start.elapsed() < timeout && !self.stats.stop.load(Ordering::Relaxed) {
+                for line in self.read_lines() {
+                    if std::mem::take(&mut first) {
+                        continue;
+                    }
+                    match LinePort::parse(&line) {
+                        Some(values) if !values.is_empty() => {
+                            return Some(values.into_iter().map(|(label, _)| label).collect())
+                        }
+                        _ => {}
+                    }
+                }
+            }
             None
         }
-        fn next(&mut self) -> bool {
-            let mut serial_buf = vec![[0b0_u8; 4]; self.internal_ports];
-            match self.port.bytes_to_read() {
-                Ok(v) if v as usize >= self.internal_ports * 4 => {
-                    for x in 0..serial_buf.len() {
-                        let _ = self.port.read_exact(&mut serial_buf[x]);
-                        if self.values[x].0.send(serial_buf[x]).is_err() {
-                            return false;
+        /// a channel for every column, they show up in the open ports list once made
+        fn add_columns(&mut self, labels: Vec<Option<String>>) {
+            for (index, label) in labels.into_iter().enumerate() {
+                let name = label.unwrap_or(format!("column {}", index + 1));
+                let channel = Arc::new(Broadcast::new(self.buffering, self.stats.clone()));
+                self.columns.push((name.clone(), channel.clone()));
+                self.arrivals
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(Box::new(MultiPort {
+                        port: channel.subscribe(),
+                        channel,
+                        name: format!("{} {}", self.name, name),
+                        settings: self.settings,
+                        stats: self.stats.clone(),
+                        console: self.console.clone(),
+                        playback: self.playback.clone(),
+                        board: None,
+                        track: None,
+                        converter: converter::be_f64,
+                    }));
+            }
+        }
+    }
+    impl Reader for LinePort {
+        fn stats(&self) -> &Stats {
+            &self.stats
+        }
+        fn start(&mut self) {
+            let labels = match self.discover(LinePort::DISCOVERY) {
+                Some(labels) => labels,
+                None => std::mem::take(&mut self.labels),
+            };
+            self.add_columns(labels);
+        }
+        fn step(&mut self) -> bool {
+            let lines = self.read_lines();
+            let time = self.stats.opened.elapsed();
+            for line in lines {
+                let Some(values) = LinePort::parse(&line) else {
+                    self.stats.malformed_lines.fetch_add(1, Ordering::Relaxed);
+                    self.stats.report(format!("malformed line {:?}", line));
+                    continue;
+                };
+                for (index, (label, value)) in values.into_iter().enumerate() {
+                    let column = match label {
+                        Some(label) => self.columns.iter().find(|(name, _)| *name == label),
+                        None => self.columns.get(index),
+                    };
+                    if let Some((_, channel)) = column {
+                        channel.send(Sample {
+                            time,
+                            bytes: value.to_be_bytes().to_vec(),
+                        });
+                    }
+                }
+            }
+            self.columns.iter().any(|(_, channel)| channel.listening())
+        }
+    }
+    /// splits what a port sends into lines
+    #[derive(Debug, Default)]
+    struct Lines {
+        /// the start of a line that has not ended yet
+        line: Vec<u8>,
+        /// the current line grew too long and is dropped up to its end
+        overlong: bool,
+        /// raw bytes as read, before they are cut into lines
+        buffer: Vec<u8>,
+    }
+    impl Lines {
+        /// longer than anything a sketch or GPS receiver prints, so not text at all
+        const MAX_LEN: usize = 4096;
+        /// the whole lines in what one read returns, the rest waits for the next read
+        fn read(
+            &mut self,
+            port: &mut Box<dyn Source>,
+            malformed: &AtomicUsize,
+            stats: &Stats,
+        ) -> Vec<String> {
+            let mut lines = vec![];
+            self.buffer.resize(PhysicalPort::READ_SIZE, 0);
+            if let Some(n) = stats.check(port.read(&mut self.buffer)) {
+                for byte in &self.buffer[..n] {
+                    match byte {
+                        b'\n' if std::mem::take(&mut self.overlong) => {}
+                        b'\n' => lines.push(
+                            String::from_utf8_lossy(&std::mem::take(&mut self.line)).to_string(),
+                        ),
+                        b'\r' => {}
+                        _ if self.overlong => {}
+                        _ if self.line.len() >= Lines::MAX_LEN => {
+                            self.line.clear();
+                            self.overlong = true;
+                            malformed.fetch_add(1, Ordering::Relaxed);
+                            stats.report(format!("line longer than {} bytes", Lines::MAX_LEN));
                         }
+                        b => self.line.push(*b),
                     }
-                    true
                 }
-                _ => true,
             }
+            lines
         }
     }
-    pub fn from_string(
-        s: &str,
-        internal_ports: usize,
-        values: Option<std::fs::File>,
-    ) -> Vec<Box<dyn Port>> {
-        let mut main_port = match values {
-            Some(v) => PhysicalPort::new(
-                Box::new(RealDummyPort::new(Some(v))),
-                internal_ports,
-                s.to_string(),
-            ),
-            None => PhysicalPort::new(
-                serialport::new(s, 9600)
-                    .open()
-                    .unwrap_or(Box::new(RealDummyPort::new(None))),
-                internal_ports,
-                s.to_string(),
-            ),
-        };
-        let _ = main_port.port.set_break();
-        let bytes_to_read: usize = main_port.port.bytes_to_read().unwrap().try_into().unwrap();
-        let _ = main_port.port.read_exact(&mut vec![0_u8; bytes_to_read]);
-        let _ = main_port.port.clear_break();
-        let return_val = (0..internal_ports)
-            .map(|_| main_port.split().unwrap())
-            .collect();
-        main_port.step_at();
-        return return_val;
-    }
-    pub struct RealDummyPort {
-        value: Item,
-        value_count: usize,
-        file: Option<std::fs::File>,
-        baud_rate: u32,
-    }
-    impl RealDummyPort {
-        fn new(file: Option<std::fs::File>) -> Self {
-            RealDummyPort {
-                value: [0; 4],
-                file: file,
-                value_count: 0,
-                baud_rate: 1000,
-            }
-        }
-    }
-    struct _AssertSend<T: Send>(T);
-    impl _AssertSend<RealDummyPort> {}
-    impl std::io::Read for RealDummyPort {
-        fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
-            match &mut self.file {
-                Some(file) => file.read(buf),
-                None => {
-                    self.value_count += 1;
-                    self.value = ((self.value_count as f32) / 100.0).sin().to_be_bytes();
-                    for x in 0..4 {
-                        buf[x] = self.value[x];
+    /// reads NMEA 0183 sentences from a GPS receiver, with one channel per `Quantity`
+    #[derive(Debug)]
+    struct NmeaPort {
+        port: Box<dyn Source>,
+        lines: Lines,
+        channels: Vec<(Quantity, Arc<Broadcast>)>,
+        track: Arc<Track>,
+        /// receivers that send GGA get their position from it only, RMC repeats it
+        gga: bool,
+        /// and those that send VTG their speed and course, RMC repeats those too
+        vtg: bool,
+        stats: Arc<Stats>,
+    }
+    impl NmeaPort {}
+    impl Reader for NmeaPort {
+        fn stats(&self) -> &Stats {
+            &self.stats
+        }
+        fn step(&mut self) -> bool {
+            let lines =
+                self.lines
+                    .read(&mut self.port, &self.stats.malformed_sentences, &self.stats);
+            let time = self.stats.opened.elapsed();
+            for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+                let mut sentence = match nmea::parse(line) {
+                    Ok(sentence) => sentence,
+                    Err(e) => {
+                        self.stats
+                            .malformed_sentences
+                            .fetch_add(1, Ordering::Relaxed);
+                        self.stats.report(e);
+                        continue;
+                    }
+                };
+                match sentence.kind.as_str() {
+                    "GGA" => self.gga = true,
+                    "VTG" => self.vtg = true,
+                    "RMC" => sentence.values.retain(|(quantity, _)| match quantity {
+                        Quantity::Latitude | Quantity::Longitude => !self.gga,
+                        Quantity::Speed | Quantity::Course => !self.vtg,
+                        _ => true,
+                    }),
+                    _ => {}
+                }
+                if let (Some(latitude), Some(longitude)) = (
+                    sentence.get(Quantity::Latitude),
+                    sentence.get(Quantity::Longitude),
+                ) {
+                    self.track.push(latitude, longitude);
+                }
+                for (quantity, value) in sentence.values {
+                    if let Some((_, channel)) = self.channels.iter().find(|(q, _)| *q == quantity) {
+                        channel.send(Sample {
+                            time,
+                            bytes: value.to_be_bytes().to_vec(),
+                        });
                     }
-                    Ok(4)
                 }
             }
+            self.channels.iter().any(|(_, channel)| channel.listening())
+        }
+    }
+    /// subscriber with one channel per topic
+    #[derive(Debug)]
+    struct MqttPort {
+        client: Client,
+        address: String,
+        filters: Vec<String>,
+        payload: Payload,
+        /// what raw payloads are read as
+        raw: converter,
+        channels: Vec<(String, Arc<Broadcast>)>,
+        buffering: Buffering,
+        arrivals: Arrivals,
+        stats: Arc<Stats>,
+    }
+    impl MqttPort {
+        fn channel(&mut self, topic: &str) -> Box<dyn Port + Send> {
+            let channel = Arc::new(Broadcast::new(self.buffering, self.stats.clone()));
+            self.channels.push((topic.to_string(), channel.clone()));
+            Box::new(MultiPort {
+                port: channel.subscribe(),
+                channel,
+                name: format!("mqtt://{}/{}", self.address, topic),
+                settings: None,
+                stats: self.stats.clone(),
+                console: None,
+                playback: None,
+                board: None,
+                track: None,
+                converter: self.payload.converter(self.raw),
+            })
+        }
+    }
+    impl Reader for MqttPort {
+        fn stats(&self) -> &Stats {
+            &self.stats
+        }
+        fn step(&mut self) -> bool {
+            if let Some(publish) = self.stats.check(self.client.next()).flatten() {
+                let time = self.stats.opened.elapsed();
+                // topics matching a wildcard get a channel with their first message
+                if !self
+                    .channels
+                    .iter()
+                    .any(|(topic, _)| *topic == publish.topic)
+                    && self
+                        .filters
+                        .iter()
+                        .any(|filter| mqtt::matches(filter, &publish.topic))
+                {
+                    let channel = self.channel(&publish.topic);
+                    self.arrivals
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(channel);
+                }
+                if let Some((topic, channel)) = self
+                    .channels
+                    .iter()
+                    .find(|(topic, _)| *topic == publish.topic)
+                {
+                    match self.payload.decode(&publish.payload, self.raw) {
+                        Ok(bytes) => {
+                            channel.send(Sample { time, bytes });
+                        }
+                        Err(e) => {
+                            self.stats.malformed_packets.fetch_add(1, Ordering::Relaxed);
+                            self.stats.report(format!("{}: {}", topic, e));
+                        }
+                    }
+                }
+            }
+            self.channels.is_empty() || self.channels.iter().any(|(_, channel)| channel.listening())
+        }
+    }
+    /// talks to a board running StandardFirmata, with one channel per reported pin
+    #[derive(Debug)]
+    struct FirmataPort {
+        port: Box<dyn Source>,
+        decoder: Decoder,
+        board: Arc<Board>,
+        pins: Vec<(Pin, Arc<Broadcast>)>,
+        /// when the version was last asked for, until the board answers
+        queried: Option<Instant>,
+        buffer: Vec<u8>,
+        stats: Arc<Stats>,
+    }
+    impl FirmataPort {
+        /// boards reset when the port opens and miss anything sent while they boot
+        const QUERY_INTERVAL: Duration = Duration::from_secs(1);
+        fn write(&mut self, bytes: &[u8]) {
+            if !bytes.is_empty() {
+                self.stats.check(self.port.write_all(bytes));
+            }
         }
     }
-    impl std::io::Write for RealDummyPort {
-        fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
-            match &mut self.file {
-                Some(file) => file.write(buf),
-                None => {
-                    for x in 0..4 {
-                        self.value[x] = buf[x]
+    impl Reader for FirmataPort {
+        fn stats(&self) -> &Stats {
+            &self.stats
+        }
+        fn step(&mut self) -> bool {
+            if self.board.state().version.is_none()
+                && self
+                    .queried
+                    .is_none_or(|queried| queried.elapsed() > FirmataPort::QUERY_INTERVAL)
+            {
+                self.queried = Some(Instant::now());
+                self.write(&firmata::query());
+            }
+            let outgoing = self.board.take_outgoing();
+            self.write(&outgoing);
+            if let Some(n @ 1..) = self.stats.check(self.port.read(&mut self.buffer)) {
+                self.decoder.push(&self.buffer[..n]);
+            }
+            let time = self.stats.opened.elapsed();
+            while let Some(message) = self.decoder.next_message() {
+                self.board.received(&message);
+                let value = |pin: &Pin| match (pin, &message) {
+                    (Pin::Analog(pin), Message::Analog(analog, value)) if pin == analog => {
+                        Some(*value as f64)
+                    }
+                    (Pin::Digital(pin), Message::Digital(port, bits)) if pin / 8 == *port => {
+                        Some((bits >> (pin % 8) & 1) as f64)
                     }
-                    Ok(4)
+                    _ => None,
+                };
+                for (pin, channel) in &self.pins {
+                    if let Some(value) = value(pin) {
+                        channel.send(Sample {
+                            time,
+                            bytes: value.to_be_bytes().to_vec(),
+                        });
+                    }
+                }
+                // a version also arrives whenever the board restarts, which forgets the reporting
+                if let Message::Version(..) = message {
+                    let pins: Vec<Pin> = self.pins.iter().map(|(pin, _)| *pin).collect();
+                    self.write(&firmata::report(&pins));
+                }
+            }
+            if self.decoder.malformed > 0 {
+                self.stats.malformed_packets.fetch_add(
+                    std::mem::take(&mut self.decoder.malformed),
+                    Ordering::Relaxed,
+                );
+                self.stats.report("malformed Firmata message, skipped");
+            }
+            self.pins.iter().any(|(_, channel)| channel.listening())
+        }
+    }
+    /// Modbus RTU master that reads every register in turn once per poll interval
+    #[derive(Debug)]
+    struct ModbusPort {
+        port: Box<dyn Source>,
+        registers: Vec<(Register, Arc<Broadcast>)>,
+        interval: Duration,
+        next_poll: Instant,
+        stats: Arc<Stats>,
+    }
+    impl ModbusPort {
+        const RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+        /// sends one request and waits for the whole answer
+        fn poll(&mut self, register: &Register) -> Result<Vec<u8>, String> {
+            self.port
+                .write_all(&register.request())
+                .map_err(|e| e.to_string())?;
+            let sent = Instant::now();
+            let mut response = vec![];
+            let mut serial_buf = [0_u8; 256];
+            while sent.elapsed() < ModbusPort::RESPONSE_TIMEOUT
+                && !self.stats.stop.load(Ordering::Relaxed)
+            {
+                if let Some(n) = self.stats.check(self.port.read(&mut serial_buf)) {
+                    response.extend_from_slice(&serial_buf[..n]);
+                }
+                if register
+                    .response_len(&response)
+                    .is_some_and(|len| response.len() >= len)
+                {
+                    return register.decode(&response);
                 }
             }
+            Err("no response".to_string())
         }
-        fn flush(&mut self) -> Result<(), std::io::Error> {
-            todo!()
+        /// throws away what arrives until the line goes quiet, so an answer that came in too
+        /// late is not taken for the next one
+        fn drain(&mut self) {
+            let started = Instant::now();
+            let mut serial_buf = [0_u8; 256];
+            while started.elapsed() < ModbusPort::RESPONSE_TIMEOUT
+                && !self.stats.stop.load(Ordering::Relaxed)
+            {
+                if !matches!(self.stats.check(self.port.read(&mut serial_buf)), Some(1..)) {
+                    return;
+                }
+            }
         }
     }
-    impl serialport::SerialPort for RealDummyPort {
-        fn name(&self) -> Option<String> {
-            Some("dummy".to_string())
+    impl Reader for ModbusPort {
+        fn stats(&self) -> &Stats {
+            &self.stats
         }
-        fn baud_rate(&self) -> Result<u32, serialport::Error> {
-            Ok(self.baud_rate)
+        fn step(&mut self) -> bool {
+            let wait = self.next_poll.saturating_duration_since(Instant::now());
+            if !wait.is_zero() {
+                std::thread::sleep(wait.min(READ_TIMEOUT));
+                return self
+                    .registers
+                    .iter()
+                    .any(|(_, channel)| channel.listening());
+            }
+            self.next_poll = (self.next_poll + self.interval).max(Instant::now());
+            let mut listening = false;
+            for (register, channel) in self.registers.clone() {
+                // a port with many dead registers still closes within one read timeout
+                if self.stats.stop.load(Ordering::Relaxed) {
+                    return false;
+                }
+                match self.poll(&register) {
+                    Ok(bytes) => {
+                        listening |= channel.send(Sample {
+                            time: self.stats.opened.elapsed(),
+                            bytes,
+                        })
+                    }
+                    Err(_) if self.stats.stop.load(Ordering::Relaxed) => return false,
+                    Err(e) => {
+                        listening |= channel.listening();
+                        channel.failed_polls.fetch_add(1, Ordering::Relaxed);
+                        self.stats.report(format!("register {}: {}", register, e));
+                        self.drain();
+                    }
+                }
+            }
+            listening
         }
-        fn data_bits(&self) -> Result<serialport::DataBits, serialport::Error> {
-            Ok(serialport::DataBits::Eight)
+    }
+    fn report_panic(stats: &Stats, panic: Box<dyn std::any::Any + Send>) {
+        let message = match panic.downcast::<String>() {
+            Ok(message) => *message,
+            Err(panic) => panic
+                .downcast::<&str>()
+                .map(|message| message.to_string())
+                .unwrap_or("unknown panic".to_string()),
+        };
+        stats.report(format!("reader thread stopped: {}", message));
+    }
+    /// a source and the settings it was opened with, serial ports are the only ones that have any
+    type Opened = (Box<dyn Source>, Option<PortSettings>);
+    /// None for dummy data, an error for a device that is there but could not be opened, e.g.
+    /// because another program has it, and for protocols the generator can't speak
+    fn open_source(
+        s: &str,
+        settings: PortSettings,
+        protocol: Protocol,
+        previous: Option<std::thread::JoinHandle<()>>,
+        stats: Arc<Stats>,
+    ) -> Result<Option<Opened>, String> {
+        if let Some(address) = tcp_address(s) {
+            return Ok(Some((Box::new(TcpSource::new(address, stats)), None)));
         }
-        fn flow_control(&self) -> Result<serialport::FlowControl, serialport::Error> {
-            Ok(serialport::FlowControl::None)
+        if let Some((address, sequenced)) = udp_address(s) {
+            return Ok(Some((
+                Box::new(UdpSource::new(address, sequenced, stats)),
+                None,
+            )));
         }
-        fn parity(&self) -> Result<serialport::Parity, serialport::Error> {
-            Ok(serialport::Parity::None)
+        if s == "stdin" {
+            return Ok(Some((Box::new(StreamSource::stdin(stats)), None)));
         }
-        fn stop_bits(&self) -> Result<serialport::StopBits, serialport::Error> {
-            todo!()
+        if let Some(path) = s.strip_prefix("file://") {
+            return Ok(Some((Box::new(StreamSource::follow(path, stats)), None)));
         }
-        fn timeout(&self) -> Duration {
-            Duration::MAX
+        let dummy = matches!(protocol, Protocol::Binary | Protocol::Text);
+        if s == "dummy" {
+            return match dummy {
+                true => Ok(None),
+                false => Err(format!("there is no dummy data for {}", protocol)),
+            };
         }
-        fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), serialport::Error> {
-            self.baud_rate = baud_rate;
-            Ok(())
+        if let Some(previous) = previous.filter(|thread| !thread.is_finished()) {
+            // the reader of the last time this port was open still has the device
+            let port = SerialSource::after(previous, s, settings, stats);
+            return Ok(Some((Box::new(port), Some(settings))));
         }
-        fn set_data_bits(
-            &mut self,
-            _data_bits: serialport::DataBits,
-        ) -> Result<(), serialport::Error> {
-            Ok(())
+        match SerialSource::open(s, settings, stats.clone()) {
+            Ok(port) => Ok(Some((Box::new(port), Some(settings)))),
+            Err(e) if !dummy || std::path::Path::new(s).exists() => {
+                Err(format!("could not open {}: {}", s, e))
+            }
+            Err(e) => {
+                stats.report(format!("could not open {}: {}, showing dummy data", s, e));
+                Ok(None)
+            }
         }
-        fn set_flow_control(
-            &mut self,
-            _flow_control: serialport::FlowControl,
-        ) -> Result<(), serialport::Error> {
-            Ok(())
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_string(
+        s: &str,
+        layout: Vec<Field>,
+        recording: Option<Recording>,
+        settings: PortSettings,
+        framing: Framing,
+        protocol: Protocol,
+        pins: &[Pin],
+        poll: &Poll,
+        payload: &Payload,
+        generator: &Generator,
+        buffering: Buffering,
+        previous: Option<PortHandle>,
+        errors: mpsc::Sender<PortError>,
+    ) -> (PortHandle, Vec<Box<dyn Port>>) {
+        let stats = Arc::new(Stats::new(s, errors));
+        let arrivals = Arrivals::default();
+        let previous = previous.and_then(|mut handle| {
+            handle.close();
+            handle.thread.take()
+        });
+        if let Some((address, filters)) = mqtt::mqtt_address(s) {
+            let raw = layout
+                .iter()
+                .find_map(|f| match f {
+                    Field::Value { converter, .. } => Some(*converter),
+                    Field::Padding(_) => None,
+                })
+                .unwrap_or(converter::be_f32);
+            let mut mqtt_port = MqttPort {
+                client: Client::new(&address, filters.clone(), stats.clone()),
+                address,
+                filters: filters.clone(),
+                payload: payload.clone(),
+                raw,
+                channels: vec![],
+                buffering,
+                arrivals: arrivals.clone(),
+                stats: stats.clone(),
+            };
+            // filters without wildcards are topics already
+            let return_val = filters
+                .iter()
+                .filter(|filter| !mqtt::is_wildcard(filter))
+                .map(|topic| mqtt_port.channel(topic) as Box<dyn Port>)
+                .collect();
+            let handle = PortHandle {
+                name: s.to_string(),
+                settings: None,
+                stats,
+                arrivals,
+                thread: Some(mqtt_port.spawn()),
+            };
+            return (handle, return_val);
+        }
+        let mut playback = None;
+        let (port, settings) = match recording {
+            Some(recording) => {
+                let frame_len = layout.iter().map(|f| f.width()).sum();
+                let replay = ReplaySource::new(recording, frame_len);
+                playback = Some(replay.playback());
+                (Box::new(replay) as Box<dyn Source>, None)
+            }
+            None => match open_source(s, settings, protocol, previous, stats.clone()) {
+                Ok(source) => source.unwrap_or_else(|| {
+                    (
+                        Box::new(GeneratorSource::new(
+                            generator.clone(),
+                            layout.clone(),
+                            framing.clone(),
+                            protocol == Protocol::Text,
+                        )),
+                        None,
+                    )
+                }),
+                Err(e) => {
+                    stats.report(e);
+                    let handle = PortHandle {
+                        name: s.to_string(),
+                        settings: None,
+                        stats,
+                        arrivals,
+                        thread: None,
+                    };
+                    return (handle, vec![]);
+                }
+            },
+        };
+        let console = Arc::new(Console::default());
+        let writable = port.writable();
+        let port = Box::new(ConsoleSource::new(port, console.clone()));
+        let console = writable.then_some(console);
+        let mut handle = PortHandle {
+            name: s.to_string(),
+            settings,
+            stats: stats.clone(),
+            arrivals,
+            thread: None,
+        };
+        if protocol == Protocol::Text {
+            // the columns are only known once the first lines are in, they arrive later
+            let line_port = LinePort {
+                port,
+                name: s.to_string(),
+                settings,
+                lines: Lines::default(),
+                columns: vec![],
+                labels: layout
+                    .iter()
+                    .filter_map(|f| match f {
+                        Field::Value { name, .. } => Some(name.clone()),
+                        Field::Padding(_) => None,
+                    })
+                    .collect(),
+                buffering,
+                arrivals: handle.arrivals.clone(),
+                stats: stats.clone(),
+                console,
+                playback,
+            };
+            handle.thread = Some(line_port.spawn());
+            return (handle, vec![]);
         }
-        fn set_parity(&mut self, _parity: serialport::Parity) -> Result<(), serialport::Error> {
-            Ok(())
+        if protocol == Protocol::Nmea {
+            let track = Arc::new(Track::default());
+            let mut nmea_port = NmeaPort {
+                port,
+                lines: Lines::default(),
+                channels: vec![],
+                track: track.clone(),
+                gga: false,
+                vtg: false,
+                stats: stats.clone(),
+            };
+            let return_val = Quantity::ALL
+                .into_iter()
+                .map(|quantity| {
+                    let channel = Arc::new(Broadcast::new(buffering, stats.clone()));
+                    nmea_port.channels.push((quantity, channel.clone()));
+                    Box::new(MultiPort {
+                        port: channel.subscribe(),
+                        channel,
+                        name: format!("{} {}", s, quantity),
+                        settings,
+                        stats: stats.clone(),
+                        console: console.clone(),
+                        playback: None,
+                        board: None,
+                        track: Some(track.clone()),
+                        converter: converter::be_f64,
+                    }) as Box<dyn Port>
+                })
+                .collect();
+            handle.thread = Some(nmea_port.spawn());
+            return (handle, return_val);
         }
-        fn set_stop_bits(
-            &mut self,
-            _stop_bits: serialport::StopBits,
-        ) -> Result<(), serialport::Error> {
-            Ok(())
+        if protocol == Protocol::Modbus {
+            let mut modbus_port = ModbusPort {
+                port,
+                registers: vec![],
+                interval: poll.interval,
+                next_poll: Instant::now(),
+                stats: stats.clone(),
+            };
+            let return_val = poll
+                .registers
+                .iter()
+                .map(|register| {
+                    let channel = Arc::new(Broadcast::new(buffering, stats.clone()));
+                    modbus_port.registers.push((*register, channel.clone()));
+                    Box::new(MultiPort {
+                        port: channel.subscribe(),
+                        channel,
+                        name: format!("{} {}", s, register),
+                        settings,
+                        stats: stats.clone(),
+                        console: console.clone(),
+                        playback: None,
+                        board: None,
+                        track: None,
+                        converter: register.converter,
+                    }) as Box<dyn Port>
+                })
+                .collect();
+            handle.thread = Some(modbus_port.spawn());
+            return (handle, return_val);
+        }
+        if protocol == Protocol::Firmata {
+            let board = Arc::new(Board::default());
+            let mut firmata_port = FirmataPort {
+                port,
+                decoder: Decoder::default(),
+                board: board.clone(),
+                pins: vec![],
+                queried: None,
+                buffer: vec![0; PhysicalPort::READ_SIZE],
+                stats: stats.clone(),
+            };
+            let return_val = pins
+                .iter()
+                .map(|pin| {
+                    let channel = Arc::new(Broadcast::new(buffering, stats.clone()));
+                    firmata_port.pins.push((*pin, channel.clone()));
+                    Box::new(MultiPort {
+                        port: channel.subscribe(),
+                        channel,
+                        name: format!("{} {}", s, pin),
+                        settings,
+                        stats: stats.clone(),
+                        console: console.clone(),
+                        playback: None,
+                        board: Some(board.clone()),
+                        track: None,
+                        converter: converter::be_f64,
+                    }) as Box<dyn Port>
+                })
+                .collect();
+            handle.thread = Some(firmata_port.spawn());
+            return (handle, return_val);
+        }
+        let internal_ports = layout
+            .iter()
+            .filter(|f| matches!(f, Field::Value { .. }))
+            .count();
+        let mut main_port = PhysicalPort::new(
+            port,
+            layout,
+            s.to_string(),
+            settings,
+            framing,
+            stats,
+            console,
+            playback,
+            buffering,
+        );
+        let return_val = (0..internal_ports)
+            .map(|_| main_port.split().unwrap())
+            .collect();
+        handle.thread = Some(main_port.spawn());
+        (handle, return_val)
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        fn sample(i: u8) -> Sample {
+            Sample {
+                time: Duration::ZERO,
+                bytes: vec![i],
+            }
         }
-        fn set_timeout(&mut self, _timeout: Duration) -> Result<(), serialport::Error> {
-            Ok(())
+        fn broadcast(overflow: Overflow) -> Broadcast {
+            Broadcast::new(
+                Buffering {
+                    capacity: 2,
+                    overflow,
+                },
+                Arc::new(Stats::new("test", mpsc::channel().0)),
+            )
         }
-        fn write_request_to_send(&mut self, _level: bool) -> Result<(), serialport::Error> {
-            Ok(())
+        fn drain(receiver: &Receiver) -> Vec<u8> {
+            std::iter::from_fn(|| receiver.try_recv())
+                .map(|sample| sample.bytes[0])
+                .collect()
         }
-        fn write_data_terminal_ready(&mut self, _level: bool) -> Result<(), serialport::Error> {
-            Ok(())
+        #[test]
+        fn dropping() {
+            for (overflow, kept) in [
+                (Overflow::DropOldest, vec![3, 4]),
+                (Overflow::DropNewest, vec![0, 1]),
+            ] {
+                let channel = broadcast(overflow);
+                let receiver = channel.subscribe();
+                for i in 0..5 {
+                    assert!(channel.send(sample(i)));
+                }
+                assert_eq!(drain(&receiver), kept);
+                assert_eq!(receiver.dropped(), 3);
+            }
         }
-        fn read_clear_to_send(&mut self) -> Result<bool, serialport::Error> {
-            Ok(true)
+        #[test]
+        fn blocking_waits_for_graphs_only() {
+            let channel = Arc::new(broadcast(Overflow::Block));
+            let graph = channel.subscribe();
+            let waiting = channel.subscribe();
+            assert_eq!(drain(&graph), vec![]);
+            let sender = channel.clone();
+            let reader = std::thread::spawn(move || {
+                for i in 0..4 {
+                    sender.send(sample(i));
+                }
+            });
+            std::thread::sleep(READ_TIMEOUT * 3);
+            assert!(!reader.is_finished());
+            let mut shown = drain(&graph);
+            reader.join().unwrap();
+            shown.extend(drain(&graph));
+            assert_eq!(shown, vec![0, 1, 2, 3]);
+            assert_eq!(graph.dropped(), 0);
+            // not shown in a graph yet, so it kept the newest samples instead of blocking
+            assert_eq!(drain(&waiting), vec![2, 3]);
+            assert_eq!(waiting.dropped(), 2);
         }
-        fn read_data_set_ready(&mut self) -> Result<bool, serialport::Error> {
-            Ok(true)
+        #[test]
+        fn closing_stops_blocking() {
+            let channel = broadcast(Overflow::Block);
+            let graph = channel.subscribe();
+            drain(&graph);
+            channel.send(sample(0));
+            channel.send(sample(1));
+            channel.stats.stop.store(true, Ordering::Relaxed);
+            channel.send(sample(2));
+            drop(graph);
+            assert!(!channel.send(sample(3)));
         }
-        fn read_ring_indicator(&mut self) -> Result<bool, serialport::Error> {
-            Ok(true)
+        /// hands out the chunks one per read, then times out like an idle device
+        #[derive(Debug)]
+        struct Script(VecDeque<Vec<u8>>);
+        impl std::io::Read for Script {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let Some(chunk) = self.0.pop_front() else {
+                    return Err(std::io::ErrorKind::TimedOut.into());
+                };
+                buf[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
         }
-        fn read_carrier_detect(&mut self) -> Result<bool, serialport::Error> {
-            Ok(true)
+        impl std::io::Write for Script {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
         }
-        fn bytes_to_read(&self) -> Result<u32, serialport::Error> {
-            Ok(1024)
-            //inf
+        impl Source for Script {}
+        /// the values of one frame, split up by a port with the given layout
+        fn values(layout: &str, frame: &[u8]) -> Vec<f64> {
+            let mut port = PhysicalPort::new(
+                Box::new(Script(VecDeque::new())),
+                crate::layout::layout::parse_layout(layout).unwrap(),
+                "test".to_string(),
+                None,
+                Framing::Raw,
+                Arc::new(Stats::new("test", mpsc::channel().0)),
+                None,
+                None,
+                Buffering::default(),
+            );
+            assert_eq!(port.frame_len(), frame.len());
+            let mut splits: Vec<_> = std::iter::from_fn(|| port.split()).collect();
+            assert!(port.send_frame(frame, Duration::ZERO));
+            splits
+                .iter_mut()
+                .map(|split| split.converter().convert(&split.next().unwrap().bytes))
+                .collect()
         }
-        fn bytes_to_write(&self) -> Result<u32, serialport::Error> {
-            Ok(4)
-            //write only try to write the last value
+        #[test]
+        fn frames_split_by_layout() {
+            let mut frame = vec![7, 0xEE, 0xEE, 0xEE];
+            frame.extend((-300_i16).to_le_bytes());
+            frame.extend([0xEE, 0xEE]);
+            frame.extend(21.5_f32.to_le_bytes());
+            frame.extend((-0.25_f32).to_le_bytes());
+            assert_eq!(values("<B3xh2x2f", &frame), vec![7.0, -300.0, 21.5, -0.25]);
+            let mut frame = 123_456_u32.to_be_bytes().to_vec();
+            frame.extend((-2_i16).to_be_bytes());
+            frame.push(0);
+            assert_eq!(values(">Ihx", &frame), vec![123_456.0, -2.0]);
+            // the byte after the first one only aligns the short
+            let mut frame = vec![5, 0xEE];
+            frame.extend(1000_i16.to_ne_bytes());
+            assert_eq!(values("@bh", &frame), vec![5.0, 1000.0]);
         }
-        fn clear(
-            &self,
-            _buffer_to_clear: serialport::ClearBuffer,
-        ) -> Result<(), serialport::Error> {
-            Ok(())
+        #[test]
+        fn overlong_lines_are_malformed() {
+            let stats = Stats::new("test", mpsc::channel().0);
+            let mut port: Box<dyn Source> = Box::new(Script(VecDeque::from([
+                b"1,2\r\n3,".to_vec(),
+                b"4\n".to_vec(),
+                vec![b'5'; Lines::MAX_LEN],
+                vec![b'6'; Lines::MAX_LEN],
+                b"7\n8\n".to_vec(),
+            ])));
+            let mut lines = Lines::default();
+            let mut read = || lines.read(&mut port, &stats.malformed_lines, &stats);
+            assert_eq!(read(), vec!["1,2"]);
+            assert_eq!(read(), vec!["3,4"]);
+            assert!(read().is_empty());
+            assert!(read().is_empty());
+            // the rest of the long line goes, the next one is fine
+            assert_eq!(read(), vec!["8"]);
+            assert_eq!(stats.malformed_lines.load(Ordering::Relaxed), 1);
         }
-        fn try_clone(&self) -> Result<Box<dyn serialport::SerialPort>, serialport::Error> {
-            Ok(Box::new(RealDummyPort::new(None)))
+        #[test]
+        fn text_columns_arrive_later() {
+            let start = Instant::now();
+            let (handle, ports) = from_string(
+                "dummy",
+                crate::layout::layout::split_layout(&[converter::be_f32; 3]),
+                None,
+                PortSettings::default(),
+                Framing::Raw,
+                Protocol::Text,
+                &[],
+                &Poll::default(),
+                &Payload::Raw,
+                &Generator::default(),
+                Buffering::default(),
+                None,
+                mpsc::channel().0,
+            );
+            assert!(start.elapsed() < LinePort::DISCOVERY / 4);
+            assert!(ports.is_empty());
+            let mut columns = vec![];
+            while columns.is_empty() && start.elapsed() < LinePort::DISCOVERY * 2 {
+                std::thread::sleep(READ_TIMEOUT);
+                columns = handle.take_channels();
+            }
+            assert_eq!(columns.len(), 3);
+            assert_eq!(columns[0].name(), "dummy column 1");
         }
-        fn set_break(&self) -> Result<(), serialport::Error> {
-            Ok(())
+        #[test]
+        fn dummy_data_for_binary_and_text_only() {
+            for (path, protocol, dummy) in [
+                ("dummy", Protocol::Binary, true),
+                ("/dev/not-plugged-in", Protocol::Text, true),
+                ("dummy", Protocol::Modbus, false),
+                ("dummy", Protocol::Firmata, false),
+                ("/dev/not-plugged-in", Protocol::Nmea, false),
+            ] {
+                let (handle, ports) = from_string(
+                    path,
+                    crate::layout::layout::split_layout(&[converter::be_f32]),
+                    None,
+                    PortSettings::default(),
+                    Framing::Raw,
+                    protocol,
+                    &[],
+                    &Poll::default(),
+                    &Payload::Raw,
+                    &Generator::default(),
+                    Buffering::default(),
+                    None,
+                    mpsc::channel().0,
+                );
+                assert_eq!(handle.running(), dummy, "{} {}", path, protocol);
+                // text columns only show up once the first lines are in
+                assert_eq!(
+                    !ports.is_empty(),
+                    dummy && protocol == Protocol::Binary,
+                    "{} {}",
+                    path,
+                    protocol
+                );
+            }
         }
-        fn clear_break(&self) -> Result<(), serialport::Error> {
-            Ok(())
+        #[test]
+        fn devices_that_are_there_but_do_not_open_are_errors() {
+            // a plain file can't be set up like a serial port, just like a device that is busy
+            let path = std::env::temp_dir().join(format!("busy-{}", std::process::id()));
+            std::fs::write(&path, b"").unwrap();
+            let path = path.to_str().unwrap().to_string();
+            let (errors, reported) = mpsc::channel();
+            let (handle, ports) = from_string(
+                &path,
+                crate::layout::layout::split_layout(&[converter::be_f32]),
+                None,
+                PortSettings::default(),
+                Framing::Raw,
+                Protocol::Binary,
+                &[],
+                &Poll::default(),
+                &Payload::Raw,
+                &Generator::default(),
+                Buffering::default(),
+                None,
+                errors,
+            );
+            std::fs::remove_file(&path).unwrap();
+            assert!(ports.is_empty());
+            assert!(!handle.running());
+            let error = reported.try_recv().unwrap();
+            assert!(error.message.starts_with("could not open"));
+            assert!(!error.message.contains("dummy"));
         }
     }
 }
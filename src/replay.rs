@@ -0,0 +1,216 @@
+pub mod replay {
+    use crate::source::source::{Source, READ_TIMEOUT};
+    use std::io::{Read, Write};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    /// a saved buffer, with the time of every frame if it was saved along with one
+    #[derive(Debug)]
+    pub struct Recording {
+        pub data: Vec<u8>,
+        /// seconds, one per frame
+        pub times: Option<Vec<f64>>,
+        /// frames per second when there are no times
+        pub sample_rate: f64,
+    }
+    impl Recording {
+        pub const SAMPLE_RATES: [f64; 6] = [1.0, 10.0, 100.0, 1000.0, 10000.0, 100000.0];
+        /// reads `path` and the times saved next to it in `path.times`, if there are any
+        pub fn open(path: &str, sample_rate: f64) -> std::io::Result<Self> {
+            let data = std::fs::read(path)?;
+            let times = std::fs::read(Recording::times_path(path))
+                .ok()
+                .map(|times| {
+                    times
+                        .chunks_exact(8)
+                        .map(|t| f64::from_le_bytes(t.try_into().unwrap()))
+                        .collect()
+                });
+            Ok(Recording {
+                data,
+                times,
+                sample_rate,
+            })
+        }
+        pub fn times_path(path: &str) -> String {
+            format!("{}.times", path)
+        }
+        /// seconds from the start of the recording to frame `index`
+        fn time(&self, index: usize) -> f64 {
+            match &self.times {
+                Some(times) => times[index] - times[0],
+                None => index as f64 / self.sample_rate.max(f64::MIN_POSITIVE),
+            }
+        }
+        /// first frame at or after `position` seconds
+        fn index_at(&self, position: f64) -> usize {
+            match &self.times {
+                Some(times) => times.partition_point(|t| t - times[0] < position),
+                None => (position * self.sample_rate).ceil() as usize,
+            }
+        }
+    }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Command {
+        Play,
+        Pause,
+        Stop,
+        Speed(f64),
+        Loop(bool),
+        Seek(f64),
+    }
+    #[derive(Debug, Clone, Copy)]
+    pub struct State {
+        pub playing: bool,
+        pub speed: f64,
+        pub looping: bool,
+        /// seconds into the recording
+        pub position: f64,
+        pub duration: f64,
+        seeked: bool,
+    }
+    /// play/pause/seek state shared between a replay's reader thread and its graphs
+    #[derive(Debug)]
+    pub struct Playback {
+        state: Mutex<State>,
+    }
+    impl Playback {
+        pub const SPEEDS: [f64; 9] = [0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 50.0, 100.0];
+        fn new(duration: f64) -> Self {
+            Playback {
+                state: Mutex::new(State {
+                    playing: true,
+                    speed: 1.0,
+                    looping: false,
+                    position: 0.0,
+                    duration,
+                    seeked: false,
+                }),
+            }
+        }
+        fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+            self.state.lock().unwrap_or_else(|e| e.into_inner())
+        }
+        pub fn state(&self) -> State {
+            *self.lock()
+        }
+        pub fn apply(&self, command: Command) {
+            let mut state = self.lock();
+            match command {
+                Command::Play => {
+                    if state.position >= state.duration {
+                        state.position = 0.0;
+                        state.seeked = true;
+                    }
+                    state.playing = true
+                }
+                Command::Pause => state.playing = false,
+                Command::Stop => {
+                    state.playing = false;
+                    state.position = 0.0;
+                    state.seeked = true;
+                }
+                Command::Speed(speed) => state.speed = speed,
+                Command::Loop(looping) => state.looping = looping,
+                Command::Seek(position) => {
+                    state.position = position.clamp(0.0, state.duration);
+                    state.seeked = true;
+                }
+            }
+        }
+    }
+    /// hands out one frame of a recording per read once the playback position passes its time
+    #[derive(Debug)]
+    pub struct ReplaySource {
+        recording: Recording,
+        frame_len: usize,
+        frames: usize,
+        playback: Arc<Playback>,
+        cursor: usize,
+        last_read: Instant,
+        recorded_at: Duration,
+    }
+    impl ReplaySource {
+        pub fn new(mut recording: Recording, frame_len: usize) -> Self {
+            let frame_len = frame_len.max(1);
+            let frames = recording.data.len() / frame_len;
+            // times saved for a different number of channels do not line up with the frames
+            if recording.times.as_ref().is_some_and(|t| t.len() != frames) {
+                recording.times = None;
+            }
+            let duration = match frames {
+                0 => 0.0,
+                frames => recording.time(frames - 1),
+            };
+            ReplaySource {
+                recording,
+                frame_len,
+                frames,
+                playback: Arc::new(Playback::new(duration)),
+                cursor: 0,
+                last_read: Instant::now(),
+                recorded_at: Duration::ZERO,
+            }
+        }
+        pub fn playback(&self) -> Arc<Playback> {
+            self.playback.clone()
+        }
+    }
+    impl Read for ReplaySource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let mut state = self.playback.lock();
+            if std::mem::take(&mut state.seeked) {
+                self.cursor = self.recording.index_at(state.position).min(self.frames);
+            }
+            if state.playing {
+                state.position += self.last_read.elapsed().as_secs_f64() * state.speed;
+            }
+            self.last_read = Instant::now();
+            if self.cursor >= self.frames {
+                if state.looping && self.frames > 0 {
+                    state.position = 0.0;
+                    self.cursor = 0;
+                } else {
+                    state.playing = false;
+                    state.position = state.duration;
+                }
+            }
+            if !state.playing || self.cursor >= self.frames {
+                drop(state);
+                std::thread::sleep(READ_TIMEOUT);
+                return Err(std::io::ErrorKind::TimedOut.into());
+            }
+            let due = self.recording.time(self.cursor);
+            if due > state.position {
+                let wait = Duration::from_secs_f64((due - state.position) / state.speed);
+                drop(state);
+                std::thread::sleep(wait.min(READ_TIMEOUT));
+                return Err(std::io::ErrorKind::TimedOut.into());
+            }
+            let start = self.cursor * self.frame_len;
+            let n = self.frame_len.min(buf.len());
+            buf[..n].copy_from_slice(&self.recording.data[start..start + n]);
+            self.recorded_at = Duration::from_secs_f64(due);
+            self.cursor += 1;
+            Ok(n)
+        }
+    }
+    impl Write for ReplaySource {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Source for ReplaySource {
+        fn datagrams(&self) -> bool {
+            true
+        }
+        fn writable(&self) -> bool {
+            false
+        }
+        fn recorded_at(&self) -> Option<Duration> {
+            Some(self.recorded_at)
+        }
+    }
+}
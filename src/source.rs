@@ -0,0 +1,847 @@
+pub mod source {
+    use crate::devices::devices::{by_id_aliases, kernel_path};
+    use crate::port::port::{PortSettings, Stats};
+    use std::fmt::Debug;
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc, Mutex, OnceLock};
+    use std::thread::JoinHandle;
+    use std::time::{Duration, Instant};
+    /// how long a network or stream read waits for data before giving the reader thread a
+    /// chance to notice it is no longer needed
+    pub const READ_TIMEOUT: Duration = Duration::from_millis(100);
+    /// anything a reader thread can pull bytes from, `read` blocks until there is data or a
+    /// timeout passes (`TimedOut`/`WouldBlock`)
+    pub trait Source: Read + Write + Send + Debug {
+        /// every `read` returns exactly one packet that holds exactly one frame
+        fn datagrams(&self) -> bool {
+            false
+        }
+        /// when the data of the last `read` was originally recorded, for replays
+        fn recorded_at(&self) -> Option<Duration> {
+            None
+        }
+        /// writes reach a device, stdin, files and replays throw them away or refuse them
+        fn writable(&self) -> bool {
+            true
+        }
+    }
+    /// a connection that is made on first use and made again once a second after it was lost,
+    /// for the sources that reconnect on their own
+    #[derive(Debug)]
+    pub struct Reconnecting<T> {
+        /// the address or path it connects to
+        pub name: String,
+        connection: Option<T>,
+        /// None before the first attempt
+        last_attempt: Option<Instant>,
+        /// a failed attempt was reported, the ones after it until it works again are not
+        reported: bool,
+        stats: Arc<Stats>,
+    }
+    impl<T> Reconnecting<T> {
+        const RETRY: Duration = Duration::from_secs(1);
+        pub fn new(name: &str, connection: Option<T>, stats: Arc<Stats>) -> Self {
+            Reconnecting {
+                name: name.to_string(),
+                last_attempt: connection.as_ref().map(|_| Instant::now()),
+                connection,
+                reported: false,
+                stats,
+            }
+        }
+        /// the connection, `connect` gets `name` and may change it to wherever it connected this
+        /// time
+        pub fn get(
+            &mut self,
+            connect: impl FnOnce(&mut String) -> std::io::Result<T>,
+        ) -> std::io::Result<&mut T> {
+            let waiting = self
+                .last_attempt
+                .is_some_and(|last_attempt| last_attempt.elapsed() < Reconnecting::<T>::RETRY);
+            if self.connection.is_none() && waiting {
+                std::thread::sleep(READ_TIMEOUT);
+            } else if self.connection.is_none() {
+                let first = self.last_attempt.is_none();
+                self.last_attempt = Some(Instant::now());
+                match connect(&mut self.name) {
+                    Ok(connection) => {
+                        self.connection = Some(connection);
+                        self.reported = false;
+                        if !first {
+                            self.stats.report(format!("reconnected to {}", self.name));
+                        }
+                    }
+                    Err(e) if !self.reported => {
+                        self.reported = true;
+                        self.stats
+                            .report(format!("could not connect to {}: {}", self.name, e));
+                    }
+                    Err(_) => {}
+                }
+                self.stats
+                    .disconnected
+                    .store(self.connection.is_none(), Ordering::Relaxed);
+            }
+            self.connection
+                .as_mut()
+                .ok_or(std::io::ErrorKind::NotConnected.into())
+        }
+        /// drops the connection on errors that mean it is gone, which a read that only timed
+        /// out is not
+        pub fn check<R>(&mut self, result: std::io::Result<R>) -> std::io::Result<R> {
+            if let Err(e) = &result {
+                if !matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock
+                        | std::io::ErrorKind::TimedOut
+                        | std::io::ErrorKind::Interrupted
+                        | std::io::ErrorKind::NotConnected
+                ) {
+                    self.connection = None;
+                    self.stats.disconnected.store(true, Ordering::Relaxed);
+                    self.stats
+                        .report(format!("{} disconnected: {}", self.name, e));
+                }
+            }
+            result
+        }
+    }
+    /// a serial device that gets reopened with the same settings when it is unplugged and the same
+    /// device (by USB VID/PID/serial number, or by path for non USB ports) shows up again
+    #[derive(Debug)]
+    pub struct SerialSource {
+        settings: PortSettings,
+        usb: Option<(u16, u16, Option<String>)>,
+        port: Reconnecting<Box<dyn serialport::SerialPort>>,
+        /// the reader that had the device before, it still holds it until its current read is over
+        previous: Option<JoinHandle<()>>,
+        stats: Arc<Stats>,
+    }
+    impl SerialSource {
+        pub fn open(
+            path: &str,
+            settings: PortSettings,
+            stats: Arc<Stats>,
+        ) -> serialport::Result<Self> {
+            let port = SerialSource::connect(path, &settings)?;
+            Ok(SerialSource {
+                settings,
+                usb: SerialSource::usb(path),
+                port: Reconnecting::new(path, Some(port), stats.clone()),
+                previous: None,
+                stats,
+            })
+        }
+        /// opens the device on the first read once `previous` let go of it, a device that still
+        /// can't be opened then stops the reader
+        pub fn after(
+            previous: JoinHandle<()>,
+            path: &str,
+            settings: PortSettings,
+            stats: Arc<Stats>,
+        ) -> Self {
+            SerialSource {
+                settings,
+                usb: None,
+                port: Reconnecting::new(path, None, stats.clone()),
+                previous: Some(previous),
+                stats,
+            }
+        }
+        fn usb(path: &str) -> Option<(u16, u16, Option<String>)> {
+            // ports are listed by their kernel name, not by the by-id alias they were opened by
+            let kernel = kernel_path(path);
+            serialport::available_ports()
+                .unwrap_or_default()
+                .into_iter()
+                .find(|p| p.port_name == kernel)
+                .and_then(|p| match p.port_type {
+                    serialport::SerialPortType::UsbPort(info) => {
+                        Some((info.vid, info.pid, info.serial_number))
+                    }
+                    _ => None,
+                })
+        }
+        /// opens the port and throws away whatever was waiting in its buffer
+        fn connect(
+            path: &str,
+            settings: &PortSettings,
+        ) -> serialport::Result<Box<dyn serialport::SerialPort>> {
+            let mut port = settings.builder(path).open()?;
+            let _ = port.set_break();
+            let bytes_to_read = serialport::SerialPort::bytes_to_read(port.as_ref())?;
+            let _ = port.read_exact(&mut vec![0_u8; bytes_to_read as usize]);
+            let _ = port.clear_break();
+            Ok(port)
+        }
+        /// where the device last seen at `path` is now, it can come back under a different name,
+        /// which is its by-id alias again if it has one
+        fn find(usb: &Option<(u16, u16, Option<String>)>, path: &str) -> Option<String> {
+            let kernel = serialport::available_ports()
+                .ok()?
+                .into_iter()
+                .find(|p| match (usb, &p.port_type) {
+                    (Some(usb), serialport::SerialPortType::UsbPort(info)) => {
+                        *usb == (info.vid, info.pid, info.serial_number.clone())
+                    }
+                    (None, _) => p.port_name == kernel_path(path),
+                    _ => false,
+                })?
+                .port_name;
+            Some(by_id_aliases().remove(&kernel).unwrap_or(kernel))
+        }
+        fn port(&mut self) -> std::io::Result<&mut Box<dyn serialport::SerialPort>> {
+            let first = match self.previous.take() {
+                Some(previous) if !previous.is_finished() => {
+                    self.previous = Some(previous);
+                    std::thread::sleep(READ_TIMEOUT);
+                    return Err(std::io::ErrorKind::NotConnected.into());
+                }
+                Some(previous) => {
+                    let _ = previous.join();
+                    true
+                }
+                None => false,
+            };
+            let (settings, usb) = (&self.settings, &mut self.usb);
+            let result = self.port.get(|path| {
+                let found = SerialSource::find(usb, path).ok_or(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "device not found",
+                ))?;
+                let port = SerialSource::connect(&found, settings)?;
+                *usb = usb.take().or_else(|| SerialSource::usb(&found));
+                *path = found;
+                Ok(port)
+            });
+            if first && result.is_err() {
+                self.stats.stop.store(true, Ordering::Relaxed);
+            }
+            result
+        }
+    }
+    impl Read for SerialSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let result = self.port()?.read(buf);
+            self.port.check(result)
+        }
+    }
+    impl Write for SerialSource {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let result = self.port()?.write(buf);
+            self.port.check(result)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            let result = self.port()?.flush();
+            self.port.check(result)
+        }
+    }
+    impl Source for SerialSource {}
+    /// like `TcpStream::connect` but gives up on each resolved address after `timeout` instead
+    /// of the much longer OS timeout
+    pub fn connect_timeout(address: &str, timeout: Duration) -> std::io::Result<TcpStream> {
+        let mut last_error = std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} does not resolve to an address", address),
+        );
+        for address in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+    /// "tcp://host:port" or a bare "host:port"
+    pub fn tcp_address(s: &str) -> Option<&str> {
+        if let Some(address) = s.strip_prefix("tcp://") {
+            return Some(address);
+        }
+        let (host, port) = s.rsplit_once(':')?;
+        (!host.is_empty() && !host.contains('/') && port.parse::<u16>().is_ok()).then_some(s)
+    }
+    /// TCP client that keeps trying to reconnect once a second while the socket is down
+    #[derive(Debug)]
+    pub struct TcpSource {
+        stream: Reconnecting<TcpStream>,
+    }
+    impl TcpSource {
+        const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+        /// connects from the first read, which happens in the reader thread
+        pub fn new(address: &str, stats: Arc<Stats>) -> Self {
+            TcpSource {
+                stream: Reconnecting::new(address, None, stats),
+            }
+        }
+        fn connect(address: &str) -> std::io::Result<TcpStream> {
+            let stream = connect_timeout(address, TcpSource::CONNECT_TIMEOUT)?;
+            stream.set_read_timeout(Some(READ_TIMEOUT))?;
+            stream.set_nodelay(true)?;
+            Ok(stream)
+        }
+    }
+    impl Read for TcpSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let result = match self
+                .stream
+                .get(|address| TcpSource::connect(address))?
+                .read(buf)
+            {
+                Ok(0) if !buf.is_empty() => Err(std::io::ErrorKind::ConnectionAborted.into()),
+                result => result,
+            };
+            self.stream.check(result)
+        }
+    }
+    impl Write for TcpSource {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let result = self
+                .stream
+                .get(|address| TcpSource::connect(address))?
+                .write(buf);
+            self.stream.check(result)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            let result = self
+                .stream
+                .get(|address| TcpSource::connect(address))?
+                .flush();
+            self.stream.check(result)
+        }
+    }
+    impl Source for TcpSource {}
+    /// "udp://bind:port", with "?seq" when every datagram starts with a little endian u32
+    /// sequence number
+    pub fn udp_address(s: &str) -> Option<(&str, bool)> {
+        let address = s.strip_prefix("udp://")?;
+        Some(match address.strip_suffix("?seq") {
+            Some(address) => (address, true),
+            None => (address, false),
+        })
+    }
+    /// listens for datagrams, dropping late ones so the graphs stay in order
+    #[derive(Debug)]
+    pub struct UdpSource {
+        address: String,
+        socket: Option<UdpSocket>,
+        last_attempt: Instant,
+        sequenced: bool,
+        next_sequence: Option<u32>,
+        peer: Option<SocketAddr>,
+        /// a whole datagram with its sequence number, kept between reads
+        packet: Vec<u8>,
+        stats: Arc<Stats>,
+    }
+    impl UdpSource {
+        const RETRY: Duration = Duration::from_secs(1);
+        /// packets further behind than this are from a device that started counting again
+        const REORDER_WINDOW: u32 = 1024;
+        pub fn new(address: &str, sequenced: bool, stats: Arc<Stats>) -> Self {
+            let _ = stats.sequenced.set(sequenced);
+            UdpSource {
+                address: address.to_string(),
+                socket: UdpSource::bind(address)
+                    .map_err(|e| stats.report(format!("could not bind {}: {}", address, e)))
+                    .ok(),
+                last_attempt: Instant::now(),
+                sequenced,
+                next_sequence: None,
+                peer: None,
+                packet: vec![],
+                stats,
+            }
+        }
+        fn bind(address: &str) -> std::io::Result<UdpSocket> {
+            let socket = UdpSocket::bind(address)?;
+            socket.set_read_timeout(Some(READ_TIMEOUT))?;
+            Ok(socket)
+        }
+        fn socket(&mut self) -> std::io::Result<&UdpSocket> {
+            if self.socket.is_none() && self.last_attempt.elapsed() < UdpSource::RETRY {
+                std::thread::sleep(READ_TIMEOUT);
+            }
+            if self.socket.is_none() && self.last_attempt.elapsed() >= UdpSource::RETRY {
+                self.last_attempt = Instant::now();
+                self.socket = UdpSource::bind(&self.address).ok();
+            }
+            self.socket
+                .as_ref()
+                .ok_or(std::io::ErrorKind::NotConnected.into())
+        }
+        /// false if the packet arrived after a later one and should be dropped
+        fn check_sequence(&mut self, sequence: u32) -> bool {
+            let expected = self.next_sequence.unwrap_or(sequence);
+            let ahead = sequence.wrapping_sub(expected);
+            let behind = expected.wrapping_sub(sequence);
+            if ahead < u32::MAX / 2 {
+                self.stats
+                    .lost_packets
+                    .fetch_add(ahead as usize, Ordering::Relaxed);
+                self.next_sequence = Some(sequence.wrapping_add(1));
+                true
+            } else if behind > UdpSource::REORDER_WINDOW {
+                self.stats.report(format!(
+                    "sequence number went back from {} to {}, the device restarted",
+                    expected.wrapping_sub(1),
+                    sequence
+                ));
+                self.next_sequence = Some(sequence.wrapping_add(1));
+                true
+            } else {
+                self.stats
+                    .out_of_order_packets
+                    .fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+        fn read_sequenced(&mut self, packet: &mut [u8], buf: &mut [u8]) -> std::io::Result<usize> {
+            loop {
+                let (n, peer) = self.socket()?.recv_from(packet)?;
+                self.peer = Some(peer);
+                if n < 4 {
+                    self.stats.rejected_frames.fetch_add(1, Ordering::Relaxed);
+                    self.stats
+                        .report(format!("datagram of {} bytes has no sequence number", n));
+                    continue;
+                }
+                if self.check_sequence(u32::from_le_bytes(packet[..4].try_into().unwrap())) {
+                    buf[..n - 4].copy_from_slice(&packet[4..n]);
+                    return Ok(n - 4);
+                }
+            }
+        }
+    }
+    impl Read for UdpSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if !self.sequenced {
+                let (n, peer) = self.socket()?.recv_from(buf)?;
+                self.peer = Some(peer);
+                return Ok(n);
+            }
+            // the sequence number has to be cut off, so the datagram goes through `packet`
+            let mut packet = std::mem::take(&mut self.packet);
+            packet.resize(buf.len() + 4, 0);
+            let result = self.read_sequenced(&mut packet, buf);
+            self.packet = packet;
+            result
+        }
+    }
+    impl Write for UdpSource {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let peer = self.peer.ok_or(std::io::ErrorKind::NotConnected)?;
+            self.socket()?.send_to(buf, peer)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Source for UdpSource {
+        fn datagrams(&self) -> bool {
+            true
+        }
+    }
+    /// follows stdin, a FIFO or a file that keeps growing (like `tail -f`) on its own thread
+    #[derive(Debug)]
+    pub struct StreamSource {
+        chunks: mpsc::Receiver<Vec<u8>>,
+        pending: Vec<u8>,
+        /// set on drop, so a thread waiting for more of a file stops even when nothing arrives
+        closed: Arc<AtomicBool>,
+    }
+    /// `O_NONBLOCK`, so opening a FIFO does not wait for a writer
+    #[cfg(target_os = "linux")]
+    const O_NONBLOCK: i32 = 0o4000;
+    #[cfg(all(unix, not(target_os = "linux")))]
+    const O_NONBLOCK: i32 = 0x0004;
+    /// the sources reading stdin with their stats, None once stdin is closed
+    type StdinReaders = Mutex<Option<Vec<(mpsc::Sender<Vec<u8>>, Arc<Stats>)>>>;
+    /// stdin is read by one thread for the whole run, so a second "stdin" port gets the same
+    /// bytes instead of competing for them, and with no port open they stay in the pipe
+    fn stdin_readers() -> &'static StdinReaders {
+        static READERS: OnceLock<StdinReaders> = OnceLock::new();
+        READERS.get_or_init(|| {
+            std::thread::spawn(|| {
+                let readers = stdin_readers();
+                let lock = || readers.lock().unwrap_or_else(|e| e.into_inner());
+                let mut buf = [0_u8; 4096];
+                let closed = loop {
+                    let n = match std::io::stdin().read(&mut buf) {
+                        Ok(0) => break "stdin closed".to_string(),
+                        Ok(n) => n,
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                        Err(e) => break e.to_string(),
+                    };
+                    loop {
+                        let delivered = lock().as_mut().is_some_and(|readers| {
+                            readers.retain(|(sender, _)| sender.send(buf[..n].to_vec()).is_ok());
+                            !readers.is_empty()
+                        });
+                        if delivered {
+                            break;
+                        }
+                        std::thread::sleep(READ_TIMEOUT);
+                    }
+                };
+                for (_, stats) in lock().take().into_iter().flatten() {
+                    stats.report(closed.clone());
+                }
+            });
+            Mutex::new(Some(vec![]))
+        })
+    }
+    impl StreamSource {
+        pub fn stdin(stats: Arc<Stats>) -> Self {
+            let (sender, chunks) = mpsc::channel();
+            match &mut *stdin_readers().lock().unwrap_or_else(|e| e.into_inner()) {
+                Some(readers) => readers.push((sender, stats)),
+                None => stats.report("stdin closed"),
+            }
+            StreamSource {
+                chunks,
+                pending: vec![],
+                closed: Arc::new(AtomicBool::new(false)),
+            }
+        }
+        /// "file://path", read from the start and then waited on for more
+        pub fn follow(path: &str, stats: Arc<Stats>) -> Self {
+            let path = path.to_string();
+            let (sender, chunks) = mpsc::channel();
+            let closed = Arc::new(AtomicBool::new(false));
+            let source = StreamSource {
+                chunks,
+                pending: vec![],
+                closed: closed.clone(),
+            };
+            std::thread::spawn(move || {
+                let mut options = std::fs::OpenOptions::new();
+                options.read(true);
+                #[cfg(unix)]
+                std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, O_NONBLOCK);
+                let mut file = loop {
+                    match options.open(&path) {
+                        Ok(file) => break file,
+                        Err(e) => stats.report(format!("could not open {}: {}", path, e)),
+                    }
+                    std::thread::sleep(Duration::from_secs(1));
+                    if closed.load(Ordering::Relaxed) {
+                        return;
+                    }
+                };
+                #[cfg(unix)]
+                let fifo = file
+                    .metadata()
+                    .is_ok_and(|m| std::os::unix::fs::FileTypeExt::is_fifo(&m.file_type()));
+                #[cfg(not(unix))]
+                let fifo = false;
+                // a FIFO reads as empty while nobody writes to it and takes the next writer on
+                // the same descriptor, a plain file just has not grown yet and after a read
+                // error it is read on from where it failed
+                let mut offset = 0;
+                while StreamSource::pump(&mut file, &sender, &closed, &mut offset, &stats) {
+                    std::thread::sleep(Duration::from_secs(1));
+                    if fifo {
+                        continue;
+                    }
+                    if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+                        stats.report(format!("could not seek in {}: {}", path, e));
+                    }
+                }
+            });
+            source
+        }
+        /// copies everything from `reader` and then waits for more, counting the bytes into
+        /// `offset`, true after a read error and false once nobody is listening any more
+        fn pump(
+            reader: &mut impl Read,
+            sender: &mpsc::Sender<Vec<u8>>,
+            closed: &AtomicBool,
+            offset: &mut u64,
+            stats: &Stats,
+        ) -> bool {
+            let mut buf = [0_u8; 4096];
+            loop {
+                if closed.load(Ordering::Relaxed) {
+                    return false;
+                }
+                match reader.read(&mut buf) {
+                    Ok(0) => std::thread::sleep(READ_TIMEOUT),
+                    Ok(n) => {
+                        if sender.send(buf[..n].to_vec()).is_err() {
+                            return false;
+                        }
+                        *offset += n as u64;
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(READ_TIMEOUT)
+                    }
+                    Err(e) => {
+                        stats.report(e.to_string());
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    impl Drop for StreamSource {
+        fn drop(&mut self) {
+            self.closed.store(true, Ordering::Relaxed);
+        }
+    }
+    impl Read for StreamSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() {
+                match self.chunks.recv_timeout(READ_TIMEOUT) {
+                    Ok(chunk) => self.pending = chunk,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        return Err(std::io::ErrorKind::TimedOut.into())
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        std::thread::sleep(READ_TIMEOUT);
+                        return Err(std::io::ErrorKind::NotConnected.into());
+                    }
+                }
+            }
+            while let Ok(chunk) = self.chunks.try_recv() {
+                self.pending.extend(chunk);
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+    impl Write for StreamSource {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::Unsupported.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Source for StreamSource {
+        fn writable(&self) -> bool {
+            false
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        #[test]
+        fn udp_sequence_numbers() {
+            let stats = Arc::new(Stats::new("udp", mpsc::channel().0));
+            let mut source = UdpSource::new("127.0.0.1:0", true, stats.clone());
+            let address = source.socket.as_ref().unwrap().local_addr().unwrap();
+            let device = UdpSocket::bind("127.0.0.1:0").unwrap();
+            // 5003 arrives before 5002, then the device reboots and counts from 0 again
+            for sequence in [5000_u32, 5001, 5003, 5002, 5004, 0, 1, 3] {
+                let mut datagram = sequence.to_le_bytes().to_vec();
+                datagram.push(sequence as u8);
+                device.send_to(&datagram, address).unwrap();
+            }
+            let mut buf = [0; 16];
+            let received: Vec<u8> = (0..7)
+                .map(|_| {
+                    assert_eq!(source.read(&mut buf).unwrap(), 1);
+                    buf[0]
+                })
+                .collect();
+            assert_eq!(
+                received,
+                [5000_u32, 5001, 5003, 5004, 0, 1, 3].map(|s| s as u8)
+            );
+            assert_eq!(stats.out_of_order_packets.load(Ordering::Relaxed), 1);
+            // 5002 came too late and 2 never came
+            assert_eq!(stats.lost_packets.load(Ordering::Relaxed), 2);
+        }
+        #[test]
+        fn tcp_reconnects() {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            // a server that hangs up after each chunk
+            std::thread::spawn(move || {
+                for chunk in [b"ab", b"cd"] {
+                    let (mut peer, _) = listener.accept().unwrap();
+                    peer.write_all(chunk).unwrap();
+                    std::thread::sleep(Duration::from_millis(200));
+                }
+            });
+            let (errors, reported) = mpsc::channel();
+            let mut source = TcpSource::new(&address, Arc::new(Stats::new("tcp", errors)));
+            let start = Instant::now();
+            let mut received = vec![];
+            while received.len() < 4 && start.elapsed() < Duration::from_secs(10) {
+                let mut buf = [0; 16];
+                if let Ok(n) = source.read(&mut buf) {
+                    received.extend_from_slice(&buf[..n]);
+                }
+            }
+            assert_eq!(received, b"abcd");
+            let messages: Vec<_> = reported.try_iter().map(|error| error.message).collect();
+            assert!(messages[0].starts_with(&format!("{} disconnected", address)));
+            assert_eq!(messages[1], format!("reconnected to {}", address));
+        }
+        #[test]
+        fn udp_restarts_beyond_the_reorder_window() {
+            let stats = Arc::new(Stats::new("udp", mpsc::channel().0));
+            let mut source = UdpSource::new("127.0.0.1:0", true, stats.clone());
+            assert!(source.check_sequence(2000));
+            // 1024 behind the expected 2001 is still a late packet
+            assert!(!source.check_sequence(2001 - 1024));
+            assert_eq!(stats.out_of_order_packets.load(Ordering::Relaxed), 1);
+            // 1025 behind is a device that started counting again
+            assert!(source.check_sequence(2001 - 1025));
+            assert_eq!(stats.out_of_order_packets.load(Ordering::Relaxed), 1);
+            assert_eq!(stats.lost_packets.load(Ordering::Relaxed), 0);
+            assert!(source.check_sequence(977));
+            assert_eq!(
+                stats.to_string(),
+                "lost packets: 0, out of order packets: 1"
+            );
+        }
+        #[test]
+        fn udp_without_sequence_numbers() {
+            let stats = Arc::new(Stats::new("udp", mpsc::channel().0));
+            UdpSource::new("127.0.0.1:0", false, stats.clone());
+            assert_eq!(
+                stats.to_string(),
+                "lost packets: not tracked, out of order packets: not tracked"
+            );
+            // everything else has no packets to lose
+            assert_eq!(Stats::new("tcp", mpsc::channel().0).to_string(), "");
+        }
+        #[test]
+        fn udp_sequence_wraps() {
+            let stats = Arc::new(Stats::new("udp", mpsc::channel().0));
+            let mut source = UdpSource::new("127.0.0.1:0", true, stats.clone());
+            assert!(source.check_sequence(u32::MAX - 1));
+            assert!(source.check_sequence(u32::MAX));
+            assert!(source.check_sequence(1));
+            assert!(!source.check_sequence(0));
+            assert_eq!(stats.lost_packets.load(Ordering::Relaxed), 1);
+            assert_eq!(stats.out_of_order_packets.load(Ordering::Relaxed), 1);
+        }
+        /// a file that fails once halfway through, the source is closed once it was read to the end
+        struct Flaky<'a>(Vec<Option<&'static [u8]>>, &'a AtomicBool);
+        impl Read for Flaky<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                match self.0.pop() {
+                    Some(Some(bytes)) => {
+                        buf[..bytes.len()].copy_from_slice(bytes);
+                        Ok(bytes.len())
+                    }
+                    Some(None) => Err(std::io::ErrorKind::Other.into()),
+                    None => {
+                        self.1.store(true, Ordering::Relaxed);
+                        Ok(0)
+                    }
+                }
+            }
+        }
+        #[test]
+        fn pump_counts_what_it_passed_on() {
+            let stats = Stats::new("file", mpsc::channel().0);
+            let (sender, chunks) = mpsc::channel();
+            let closed = AtomicBool::new(false);
+            let mut offset = 0;
+            let mut file = Flaky(vec![Some(b"ef"), None, Some(b"cd"), Some(b"ab")], &closed);
+            assert!(StreamSource::pump(
+                &mut file,
+                &sender,
+                &closed,
+                &mut offset,
+                &stats
+            ));
+            assert_eq!(offset, 4);
+            assert!(!StreamSource::pump(
+                &mut file,
+                &sender,
+                &closed,
+                &mut offset,
+                &stats
+            ));
+            assert_eq!(offset, 6);
+            assert_eq!(chunks.try_iter().flatten().collect::<Vec<u8>>(), b"abcdef");
+            drop(chunks);
+            let closed = AtomicBool::new(false);
+            let mut file = Flaky(vec![Some(b"gh")], &closed);
+            assert!(!StreamSource::pump(
+                &mut file,
+                &sender,
+                &closed,
+                &mut offset,
+                &stats
+            ));
+            assert_eq!(offset, 6);
+        }
+        #[cfg(unix)]
+        #[test]
+        fn following_a_fifo_lets_go_of_it() {
+            let path = std::env::temp_dir().join(format!("fifo-{}", std::process::id()));
+            let made = std::process::Command::new("mkfifo").arg(&path).status();
+            assert!(made.is_ok_and(|status| status.success()));
+            // without a writer there is nothing to read, but the source does not hang on it
+            let stats = Arc::new(Stats::new("fifo", mpsc::channel().0));
+            let mut source = StreamSource::follow(path.to_str().unwrap(), stats);
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true);
+            std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, O_NONBLOCK);
+            // a FIFO can only be opened like this for writing while something reads it
+            let writer = || {
+                let start = Instant::now();
+                loop {
+                    match options.open(&path) {
+                        Ok(writer) => return Some(writer),
+                        Err(_) if start.elapsed() > Duration::from_secs(5) => return None,
+                        Err(_) => std::thread::sleep(READ_TIMEOUT),
+                    }
+                }
+            };
+            for chunk in [b"ab", b"cd"] {
+                writer().unwrap().write_all(chunk).unwrap();
+                let mut buf = [0; 16];
+                let n = loop {
+                    if let Ok(n @ 1..) = source.read(&mut buf) {
+                        break n;
+                    }
+                };
+                assert_eq!(&buf[..n], chunk);
+            }
+            drop(source);
+            let start = Instant::now();
+            while options.open(&path).is_ok() && start.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(READ_TIMEOUT);
+            }
+            assert!(options.open(&path).is_err());
+            std::fs::remove_file(&path).unwrap();
+        }
+        #[test]
+        fn following_a_growing_file() {
+            let path = std::env::temp_dir().join(format!("follow-{}", std::process::id()));
+            std::fs::write(&path, b"abc").unwrap();
+            let stats = Arc::new(Stats::new("file", mpsc::channel().0));
+            let mut source = StreamSource::follow(path.to_str().unwrap(), stats);
+            let mut read = |len: usize| {
+                let mut received = vec![];
+                let mut buf = [0; 16];
+                while received.len() < len {
+                    if let Ok(n) = source.read(&mut buf) {
+                        received.extend_from_slice(&buf[..n]);
+                    }
+                }
+                received
+            };
+            assert_eq!(read(3), b"abc");
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .write_all(b"def")
+                .unwrap();
+            assert_eq!(read(3), b"def");
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}